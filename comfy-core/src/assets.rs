@@ -13,6 +13,82 @@ pub fn is_texture_loaded(id: &str) -> bool {
     ASSETS.borrow().textures.contains_key(id)
 }
 
+/// Whether the texture was decoded, e.g. one loaded with
+/// `load_texture_async`. It's uploaded to the GPU at the start of the next
+/// frame.
+pub fn is_texture_handle_loaded(handle: TextureHandle) -> bool {
+    ASSETS.borrow().texture_image_map.lock().contains_key(&handle)
+}
+
+/// Starts loading the image at `path` in the background and returns its
+/// handle right away. Until the image is loaded, draw calls using the handle
+/// render the error texture as a placeholder, check
+/// `is_texture_handle_loaded` to show a loading state instead.
+///
+/// Unlike the textures loaded through the `AssetSource`, `path` is read
+/// directly from the filesystem, or fetched as a URL relative to the page on
+/// wasm. The texture can also be looked up with `texture_id(path)`.
+pub fn load_texture_async(path: &str) -> TextureHandle {
+    let handle = texture_id_unchecked(path);
+
+    let mut assets = ASSETS.borrow_mut();
+
+    if assets.textures.contains_key(path) {
+        return handle;
+    }
+
+    assets.textures.insert(path.to_string(), handle);
+    inc_assets_queued(1);
+
+    let sender = assets.asset_loader.texture_data_send.clone();
+    let path = path.to_string();
+
+    cfg_if! {
+        if #[cfg(target_arch = "wasm32")] {
+            wasm_bindgen_futures::spawn_local(async move {
+                match fetch_bytes(&path).await {
+                    Ok(bytes) => {
+                        let data = TextureAssetData { path, handle, bytes };
+                        sender.lock().send(data).log_err();
+                    }
+                    Err(err) => error!("Failed to fetch {}: {:?}", path, err),
+                }
+            });
+        } else {
+            assets.asset_loader.thread_pool.spawn(move || {
+                match std::fs::read(&path) {
+                    Ok(bytes) => {
+                        let data = TextureAssetData { path, handle, bytes };
+                        sender.lock().send(data).log_err();
+                    }
+                    Err(err) => error!("Failed to load {}: {}", path, err),
+                }
+            });
+        }
+    }
+
+    handle
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn fetch_bytes(url: &str) -> Result<Vec<u8>, wasm_bindgen::JsValue> {
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_futures::JsFuture;
+
+    let window = web_sys::window().ok_or("no window")?;
+
+    let response: web_sys::Response =
+        JsFuture::from(window.fetch_with_str(url)).await?.dyn_into()?;
+
+    if !response.ok() {
+        return Err(format!("HTTP {}", response.status()).into());
+    }
+
+    let buffer = JsFuture::from(response.array_buffer()?).await?;
+
+    Ok(js_sys::Uint8Array::new(&buffer).to_vec())
+}
+
 pub struct AssetSource {
     pub dir: &'static include_dir::Dir<'static>,
     pub base_path: BasePathFn,
@@ -18,11 +18,17 @@ pub fn px() -> f32 {
     f32::from_bits(PX.load(Ordering::SeqCst))
 }
 
+/// Mouse position in physical pixels as of the newest `CursorMoved` event.
+/// When several events arrive between two frames only the last one counts,
+/// see `mouse_moved_this_frame()` to tell whether there were any.
 pub fn mouse_screen() -> Vec2 {
     let pos = GLOBAL_STATE.borrow().mouse_position;
     Vec2::new(pos.x, pos.y)
 }
 
+/// Mouse position in world space, updated at the start of each frame from
+/// `mouse_screen()` through the main camera and `viewport_rect()`. Stays put
+/// while the mouse is locked.
 pub fn mouse_world() -> Vec2 {
     GLOBAL_STATE.borrow().mouse_world
 }
@@ -31,6 +37,32 @@ pub fn screen_width() -> f32 {
     GLOBAL_STATE.borrow().screen_size.x
 }
 
+/// The area of the window in physical pixels the game is rendered into. This
+/// is the whole window unless `ResolutionConfig::Aspect` or
+/// `GameConfig::integer_scaling` is used, in which case it excludes the
+/// black bars.
+///
+/// To map a window position (e.g. `mouse_screen()`) into design space, use
+/// `(pos - rect.top_left()) / rect.size * design`.
+pub fn viewport_rect() -> Rect {
+    screen_viewport(&GLOBAL_STATE.borrow())
+}
+
+/// The integer multiple of `GameConfig::integer_scaling` the game is shown
+/// at, `1` when integer scaling is off.
+pub fn pixel_scale() -> u32 {
+    GLOBAL_STATE.borrow().pixel_scale.max(1)
+}
+
+fn screen_viewport(state: &GlobalState) -> Rect {
+    state.viewport.unwrap_or(Rect::from_xywh(
+        0.0,
+        0.0,
+        state.screen_size.x,
+        state.screen_size.y,
+    ))
+}
+
 pub fn aspect_ratio() -> f32 {
     MAIN_CAMERA.borrow().aspect_ratio
 }
@@ -62,16 +94,35 @@ pub fn egui_scale_factor() -> f32 {
     GLOBAL_STATE.borrow().egui_scale_factor
 }
 
+/// Scales the egui UI on top of the OS scale factor, e.g. for an
+/// accessibility zoom setting. `egui_scale_factor()` ends up being
+/// `scale * window.scale_factor()`, and moving the window to a monitor with a
+/// different scale factor keeps the user scale. Applied at the start of the
+/// next frame.
+pub fn set_ui_scale(scale: f32) {
+    egui().set_zoom_factor(scale.max(0.1));
+}
+
+/// The user UI scale set with `set_ui_scale`, 1.0 by default.
+pub fn ui_scale() -> f32 {
+    egui().zoom_factor()
+}
+
 pub fn world_to_gl_screen(position: Vec2) -> Vec2 {
     let mut screen = world_to_screen(position);
     screen.y = screen_height() - screen.y;
     screen
 }
 
+/// Maps a world position to physical window pixels through the main camera,
+/// taking the letterboxed viewport into account. Divide by
+/// `egui_scale_factor()` for egui points.
 pub fn world_to_screen(position: Vec2) -> Vec2 {
     main_camera().world_to_screen(position)
 }
 
+/// Inverse of `world_to_screen`, `position` is in physical window pixels
+/// like `mouse_screen()`.
 pub fn screen_to_world(position: Vec2) -> Vec2 {
     main_camera().screen_to_world(position)
 }
@@ -298,12 +349,26 @@ impl MainCamera {
     }
 
     pub fn screen_to_world(&self, position: Vec2) -> Vec2 {
+        self.screen_to_world_in(
+            screen_viewport(&GLOBAL_STATE.borrow()),
+            position,
+        )
+    }
+
+    pub fn world_to_screen(&self, position: Vec2) -> Vec2 {
+        self.world_to_screen_in(
+            screen_viewport(&GLOBAL_STATE.borrow()),
+            position,
+        )
+    }
+
+    /// Like `screen_to_world` with the game shown in `screen` instead of the
+    /// current viewport.
+    fn screen_to_world_in(&self, screen: Rect, position: Vec2) -> Vec2 {
         let viewport = self.world_viewport();
         let camera_center = self.center;
 
-        let state = GLOBAL_STATE.borrow();
-
-        let normalized = position / state.screen_size;
+        let normalized = (position - screen.top_left()) / screen.size;
         let normalized = vec2(normalized.x, 1.0 - normalized.y);
 
         let world_unoffset = (normalized - 0.5) * viewport;
@@ -311,18 +376,16 @@ impl MainCamera {
         world_unoffset + camera_center
     }
 
-    pub fn world_to_screen(&self, position: Vec2) -> Vec2 {
+    fn world_to_screen_in(&self, screen: Rect, position: Vec2) -> Vec2 {
         let viewport = self.world_viewport();
 
         let position = position - self.center;
 
-        let state = GLOBAL_STATE.borrow();
-
         let moved = position + viewport / 2.0;
         let normalized = moved / viewport;
         let normalized = vec2(normalized.x, 1.0 - normalized.y);
 
-        normalized * state.screen_size
+        screen.top_left() + normalized * screen.size
         // vec2(
         //     normalized.x * state.screen_size.x / state.egui_scale_factor,
         //     normalized.y * state.screen_size.y / state.egui_scale_factor,
@@ -474,3 +537,44 @@ impl Size {
         vec2(self.width.to_world(Axis::X), self.height.to_world(Axis::Y))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn screen_and_world_positions_round_trip() {
+        let mut camera = MainCamera::new(Vec2::ZERO, 800.0);
+        camera.aspect_ratio = 800.0 / 600.0;
+
+        let close = |a: Vec2, b: Vec2| a.distance(b) < 1e-3;
+        let round_trip = |screen: Rect, point: Vec2| {
+            let screen_point = camera.world_to_screen_in(screen, point);
+            camera.screen_to_world_in(screen, screen_point)
+        };
+
+        // One world unit per pixel, centered, with Y pointing up.
+        let window = Rect::from_xywh(0.0, 0.0, 800.0, 600.0);
+
+        assert!(close(
+            camera.screen_to_world_in(window, vec2(400.0, 300.0)),
+            Vec2::ZERO
+        ));
+        assert!(close(
+            camera.world_to_screen_in(window, vec2(100.0, 50.0)),
+            vec2(500.0, 250.0)
+        ));
+
+        let point = vec2(-123.0, 45.0);
+        assert!(close(round_trip(window, point), point));
+
+        // Letterboxed into a 400x300 area in the middle of the window.
+        let letterboxed = Rect::from_xywh(200.0, 150.0, 400.0, 300.0);
+
+        assert!(close(
+            camera.screen_to_world_in(letterboxed, vec2(200.0, 150.0)),
+            vec2(-400.0, 300.0)
+        ));
+        assert!(close(round_trip(letterboxed, point), point));
+    }
+}
@@ -0,0 +1,122 @@
+use crate::*;
+
+#[cfg(not(target_arch = "wasm32"))]
+thread_local! {
+    static CLIPBOARD: RefCell<Option<egui_winit::clipboard::Clipboard>> =
+        const { RefCell::new(None) };
+}
+
+/// Last text we set or read, used when there's no system clipboard (e.g.
+/// running headless), and on wasm where reading is async.
+static CLIPBOARD_TEXT: Lazy<Mutex<Option<String>>> =
+    Lazy::new(|| Mutex::new(None));
+
+/// Connects the clipboard functions to the system clipboard of the window's
+/// display. Called by the renderer when it's created.
+///
+/// Without the `clipboard` feature egui-winit only keeps an in-process
+/// clipboard that isn't shared with other applications.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn init_clipboard(window: &Window) {
+    CLIPBOARD.with(|clipboard| {
+        *clipboard.borrow_mut() =
+            Some(egui_winit::clipboard::Clipboard::new(window));
+    });
+}
+
+/// Returns the text currently on the clipboard, if any.
+///
+/// On wasm this goes through the async Clipboard API, which first needs the
+/// browser to grant access. Until a read finishes this returns the last text
+/// we know of, or `None`, so keep calling it, e.g. once per frame while a
+/// paste is pending.
+pub fn clipboard_get() -> Option<String> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let text = CLIPBOARD.with(|clipboard| {
+            clipboard.borrow_mut().as_mut().and_then(|x| x.get())
+        });
+
+        if text.is_some() {
+            return text;
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    web::read_text();
+
+    CLIPBOARD_TEXT.lock().clone()
+}
+
+/// Puts `text` on the clipboard, replacing its contents.
+pub fn clipboard_set(text: &str) {
+    *CLIPBOARD_TEXT.lock() = Some(text.to_string());
+
+    #[cfg(not(target_arch = "wasm32"))]
+    CLIPBOARD.with(|clipboard| {
+        if let Some(clipboard) = clipboard.borrow_mut().as_mut() {
+            clipboard.set(text.to_string());
+        }
+    });
+
+    #[cfg(target_arch = "wasm32")]
+    web::write_text(text);
+}
+
+#[cfg(target_arch = "wasm32")]
+mod web {
+    use wasm_bindgen::{JsCast, JsValue};
+    use wasm_bindgen_futures::JsFuture;
+
+    use super::*;
+
+    // `web_sys::Clipboard` is behind `web_sys_unstable_apis`, so the API is
+    // looked up dynamically instead.
+    fn call(method: &str, args: &[JsValue]) -> Option<js_sys::Promise> {
+        let window = web_sys::window()?;
+        let navigator =
+            js_sys::Reflect::get(&window, &"navigator".into()).ok()?;
+        let clipboard =
+            js_sys::Reflect::get(&navigator, &"clipboard".into()).ok()?;
+
+        if clipboard.is_undefined() {
+            return None;
+        }
+
+        let function: js_sys::Function =
+            js_sys::Reflect::get(&clipboard, &method.into())
+                .ok()?
+                .dyn_into()
+                .ok()?;
+
+        let args = args.iter().collect::<js_sys::Array>();
+
+        function.apply(&clipboard, &args).ok()?.dyn_into().ok()
+    }
+
+    pub fn read_text() {
+        let Some(promise) = call("readText", &[]) else {
+            return;
+        };
+
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Ok(text) = JsFuture::from(promise).await {
+                if let Some(text) = text.as_string() {
+                    *CLIPBOARD_TEXT.lock() = Some(text);
+                }
+            }
+        });
+    }
+
+    pub fn write_text(text: &str) {
+        let Some(promise) = call("writeText", &[text.into()]) else {
+            return;
+        };
+
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = JsFuture::from(promise).await {
+                warn!("Failed to write to the clipboard: {:?}", err);
+            }
+        });
+    }
+}
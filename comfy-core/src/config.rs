@@ -6,6 +6,12 @@ pub const COMBAT_TEXT_LIFETIME: f32 = 0.4;
 pub enum ResolutionConfig {
     Physical(u32, u32),
     Logical(u32, u32),
+    /// Renders the game at the aspect ratio of the `design` resolution,
+    /// scaled to fit the window with black bars filling the rest. See
+    /// `viewport_rect()` for the area the game ends up in.
+    Aspect {
+        design: (u32, u32),
+    },
 }
 
 impl ResolutionConfig {
@@ -13,6 +19,7 @@ impl ResolutionConfig {
         match self {
             Self::Physical(w, _) => *w,
             Self::Logical(w, _) => *w,
+            Self::Aspect { design: (w, _) } => *w,
         }
     }
 
@@ -20,6 +27,17 @@ impl ResolutionConfig {
         match self {
             Self::Physical(_, h) => *h,
             Self::Logical(_, h) => *h,
+            Self::Aspect { design: (_, h) } => *h,
+        }
+    }
+
+    /// Size in physical pixels on a display with `scale_factor`.
+    pub fn to_physical(&self, scale_factor: f32) -> UVec2 {
+        match *self {
+            Self::Physical(w, h) => uvec2(w, h),
+            Self::Logical(w, h) | Self::Aspect { design: (w, h) } => {
+                (vec2(w as f32, h as f32) * scale_factor).round().as_uvec2()
+            }
         }
     }
 
@@ -27,7 +45,8 @@ impl ResolutionConfig {
         const MIN_WINDOW_SIZE: u32 = 1;
         match self {
             ResolutionConfig::Physical(w, h) |
-            ResolutionConfig::Logical(w, h)
+            ResolutionConfig::Logical(w, h) |
+            ResolutionConfig::Aspect { design: (w, h) }
                 if *w == 0 || *h == 0 =>
             {
                 *w = MIN_WINDOW_SIZE;
@@ -38,6 +57,228 @@ impl ResolutionConfig {
 
         *self
     }
+
+    /// The part of a window of `window_size` physical pixels the game is
+    /// rendered into, `None` unless this is `Aspect`.
+    pub fn letterbox(&self, window_size: UVec2) -> Option<Rect> {
+        let Self::Aspect { design: (w, h) } = *self else {
+            return None;
+        };
+
+        if w == 0 || h == 0 || window_size.x == 0 || window_size.y == 0 {
+            return None;
+        }
+
+        let window = window_size.as_vec2();
+        let design = vec2(w as f32, h as f32);
+
+        let scale = (window.x / design.x).min(window.y / design.y);
+        let size = (design * scale).round().min(window);
+        let offset = ((window - size) / 2.0).floor();
+
+        Some(Rect::from_xywh(offset.x, offset.y, size.x, size.y))
+    }
+}
+
+/// The largest integer multiple of `base` that fits into a window of
+/// `window_size` physical pixels, centered, along with the multiple. `None`
+/// when the window is smaller than `base`.
+pub fn integer_letterbox(
+    base: UVec2,
+    window_size: UVec2,
+) -> Option<(Rect, u32)> {
+    if base.x == 0 || base.y == 0 {
+        return None;
+    }
+
+    let scale = (window_size.x / base.x).min(window_size.y / base.y);
+
+    if scale == 0 {
+        return None;
+    }
+
+    let size = base * scale;
+    let offset = (window_size - size) / 2;
+
+    Some((
+        Rect::from_xywh(
+            offset.x as f32,
+            offset.y as f32,
+            size.x as f32,
+            size.y as f32,
+        ),
+        scale,
+    ))
+}
+
+/// The size closest to `size` with a width to height ratio of `ratio`,
+/// changing only the width or only the height, whichever needs the smaller
+/// change.
+pub fn fit_aspect_ratio(size: UVec2, ratio: f32) -> UVec2 {
+    if ratio <= 0.0 || size.x == 0 || size.y == 0 {
+        return size;
+    }
+
+    let width = ((size.y as f32 * ratio).round() as u32).max(1);
+    let height = ((size.x as f32 / ratio).round() as u32).max(1);
+
+    if width.abs_diff(size.x) <= height.abs_diff(size.y) {
+        uvec2(width, size.y)
+    } else {
+        uvec2(size.x, height)
+    }
+}
+
+/// How often the game loop runs frames.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum LoopMode {
+    /// Runs frames back to back, limited by `target_framerate`.
+    #[default]
+    Continuous,
+    /// Only runs a frame after window events (input, resize, ...), when
+    /// `request_redraw()` was called or when egui needs to animate. Sleeps
+    /// otherwise, which saves a lot of CPU and battery for turn based games
+    /// and tools.
+    ///
+    /// `max_idle` is the longest time in seconds between two frames, so that
+    /// timers and gamepads (which are only polled during frames) still get a
+    /// chance to update. `None` waits indefinitely.
+    Reactive { max_idle: Option<f32> },
+}
+
+/// When the just pressed/released state of keys, mouse buttons and gamepad
+/// buttons is cleared, see `GameConfig::just_pressed_mode`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum JustPressedMode {
+    /// Cleared at the end of every frame.
+    #[default]
+    PerFrame,
+    /// Kept until the first `GameLoop::update` tick after the press, so a
+    /// game running at a low `update_rate` doesn't miss presses that happen
+    /// on frames without an update. Each press is seen by exactly one tick.
+    UntilUpdate,
+    /// Like `UntilUpdate`, but for `GameLoop::fixed_update` ticks. Behaves
+    /// like `PerFrame` when fixed updates are disabled with a
+    /// `fixed_timestep` of zero.
+    UntilFixedUpdate,
+}
+
+/// Runs a fixed number of frames as fast as possible and prints the frame
+/// time statistics, see `GameConfig::benchmark`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BenchmarkConfig {
+    /// Number of frames to run before printing the results and quitting.
+    pub frames: u64,
+}
+
+/// Graphics APIs wgpu may use, see `GpuConfig`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum GpuBackends {
+    /// Whatever works on the platform, including the OpenGL fallback.
+    #[default]
+    All,
+    /// Vulkan, Metal and DX12, without the fallbacks. Finds no adapter in the
+    /// web build, which only has WebGL2.
+    Primary,
+    Vulkan,
+    Metal,
+    Dx12,
+    /// OpenGL, or WebGL2 on the web.
+    Gl,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum PowerPreference {
+    /// Leaves the choice to wgpu.
+    None,
+    /// Prefers the integrated GPU, e.g. to save battery.
+    LowPower,
+    /// Prefers the discrete GPU on laptops with two of them.
+    #[default]
+    HighPerformance,
+}
+
+/// Which GPU the renderer runs on. The `WGPU_BACKEND` and `WGPU_POWER_PREF`
+/// environment variables override `backends` and `power_preference`, the
+/// adapter that ends up being used is logged at startup.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct GpuConfig {
+    pub backends: GpuBackends,
+    pub power_preference: PowerPreference,
+    /// Only accepts a software adapter, e.g. for testing without a GPU.
+    pub force_fallback: bool,
+}
+
+/// How the frame limiter waits for the next frame, see `PacingConfig`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum PacingMode {
+    /// Sleeps until shortly before the next frame and spins for the rest,
+    /// which keeps a core busy for up to `native_accuracy` every frame.
+    #[default]
+    Hybrid,
+    /// Spins for the whole wait. This gives the most consistent frame times
+    /// (well under a millisecond of jitter), but pins a core at 100% for as
+    /// long as the game runs, draining laptop batteries and heating up the
+    /// CPU to the point it may throttle. Only worth it when frame jitter
+    /// really matters, e.g. for rhythm games.
+    Spin,
+    /// Only uses the OS sleep and never spins. Uses the least power, but
+    /// frames can be late by the OS timer resolution, which is around 1ms on
+    /// Linux and macOS and can be much more on Windows.
+    Sleep,
+}
+
+/// Settings for the desktop frame limiter, which sleeps at the end of each
+/// frame to hit `target_framerate`. Has no effect on wasm, where the browser
+/// paces the frames.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PacingConfig {
+    pub mode: PacingMode,
+    /// How long before the next frame `PacingMode::Hybrid` stops sleeping
+    /// and starts spinning, i.e. how much the OS sleep is expected to
+    /// overshoot. Larger values reduce jitter with coarse OS timers, at the
+    /// cost of more time spent spinning. `None` uses the `spin_sleep`
+    /// platform default.
+    pub native_accuracy: Option<Duration>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl PacingConfig {
+    /// Builds the limiter, a `target_rate` of `0.0` doesn't limit at all.
+    pub fn loop_helper(&self, target_rate: f32) -> LoopHelper {
+        let builder = LoopHelper::builder();
+
+        let builder = match (self.mode, self.native_accuracy) {
+            // Never trust the OS sleep, so the whole wait is spent spinning.
+            (PacingMode::Spin, _) => builder.native_accuracy_ns(999_999_999),
+            (_, Some(accuracy)) => {
+                builder.native_accuracy_ns(
+                    accuracy.as_nanos().min(999_999_999) as u32
+                )
+            }
+            (_, None) => builder,
+        };
+
+        builder.build_with_target_rate(Self::rate(target_rate))
+    }
+
+    /// Converts a framerate where `0.0` means uncapped into the rate used by
+    /// `LoopHelper`.
+    pub fn rate(target_rate: f32) -> f64 {
+        if target_rate > 0.0 {
+            target_rate as f64
+        } else {
+            f64::INFINITY
+        }
+    }
+
+    /// Waits until the next frame is due.
+    pub fn sleep(&self, loop_helper: &mut LoopHelper) {
+        match self.mode {
+            PacingMode::Hybrid | PacingMode::Spin => loop_helper.loop_sleep(),
+            PacingMode::Sleep => loop_helper.loop_sleep_no_spin(),
+        }
+    }
 }
 
 static GAME_CONFIG: OnceCell<AtomicRefCell<GameConfig>> = OnceCell::new();
@@ -72,6 +313,20 @@ pub fn game_config_mut() -> AtomicRefMut<'static, GameConfig> {
         .borrow_mut()
 }
 
+/// `GameConfig::max_frame_latency` clamped to the supported `1..=3` range.
+pub fn max_frame_latency() -> Option<u32> {
+    game_config().max_frame_latency.map(|x| x.clamp(1, 3))
+}
+
+/// Returns the `(min, max)` bounds the frame delta is clamped to, taken from
+/// `min_delta` and `max_delta` in the config.
+pub fn delta_clamp_range() -> (f32, f32) {
+    let config = game_config();
+    let min = config.min_delta.max(0.0);
+
+    (min, config.max_delta.max(min))
+}
+
 #[derive(Clone, Debug)]
 pub struct GameConfig {
     pub game_name: String,
@@ -79,9 +334,163 @@ pub struct GameConfig {
 
     pub resolution: ResolutionConfig,
     pub min_resolution: ResolutionConfig,
-
+    /// Largest size the window can be resized to, `None` for no limit.
+    pub max_resolution: Option<ResolutionConfig>,
+    /// Width to height ratio the window is kept at while resizing, e.g.
+    /// `Some(16.0 / 9.0)`. The window snaps to the closest matching size
+    /// after every resize, except while maximized or fullscreen.
+    pub aspect_ratio: Option<f32>,
+    /// Base resolution for pixel art games. The game is only ever shown at
+    /// integer multiples of it, the largest one that fits the window, with
+    /// black bars filling the rest. See `pixel_scale()` for the multiple and
+    /// `viewport_rect()` for the area the game ends up in. Takes precedence
+    /// over `ResolutionConfig::Aspect`.
+    ///
+    /// Windows smaller than the base resolution scale it down to fit at its
+    /// aspect ratio instead.
+    pub integer_scaling: Option<UVec2>,
+
+    /// Icon set on the window at startup, see `set_window_icon` for changing
+    /// it at runtime.
+    pub window_icon: Option<WindowIcon>,
+    pub window_state: WindowStartState,
+    /// Whether the window has the OS title bar and borders. Turn it off for
+    /// a custom title bar, see `set_decorations` and `start_window_drag`.
+    pub decorations: bool,
+    /// Cursor shown over the window from the first frame, see `set_cursor`
+    /// for changing it at runtime.
+    pub cursor_icon: CursorConfig,
+
+    /// Initial framerate limit, `0` for uncapped and
+    /// `GameConfig::AUTO_FRAMERATE` for the refresh rate of the monitor. See
+    /// `set_target_framerate` for changing it at runtime.
     pub target_framerate: u32,
+    pub loop_mode: LoopMode,
+    /// Framerate to throttle to while the window doesn't have focus, `None`
+    /// keeps using `target_framerate`.
+    pub unfocused_framerate: Option<u32>,
     pub vsync_enabled: bool,
+    /// How many frames the CPU may get ahead of the GPU, in `1..=3`. `1`
+    /// gives the lowest input latency at the cost of some throughput, since
+    /// the CPU waits for the GPU to finish every frame. `None` leaves it to
+    /// the driver.
+    ///
+    /// wgpu 0.18 has no surface setting for this, so it's enforced by
+    /// waiting on the GPU after presenting. The driver and compositor can
+    /// still queue frames on top of that, and on the web the wait does
+    /// nothing, so treat it as a hint.
+    pub max_frame_latency: Option<u32>,
+    /// Skips the CPU side frame limiter while `set_present_mode` has switched
+    /// to `PresentMode::Fifo`, letting vsync alone pace the frames.
+    pub fifo_disables_frame_limiter: bool,
+    pub pacing: PacingConfig,
+    pub gpu: GpuConfig,
+    /// Runs a frame as soon as the window is resized instead of waiting for
+    /// the end of the event loop iteration, so the game keeps animating
+    /// while the window edge is dragged.
+    ///
+    /// This matters most on Windows, where the event loop is stuck in the
+    /// OS resize loop during the drag and only redraws get through. Other
+    /// platforms mostly keep running the loop while resizing, so there it
+    /// only makes the new size show up a little earlier. Resizes are still
+    /// coalesced, a frame only runs if the size changed since the last one.
+    pub render_during_resize: bool,
+    /// Uncapped mode for performance measurements. Disables the frame
+    /// limiter, switches to `PresentMode::Immediate` (if the surface
+    /// supports it), and after `frames` frames prints `frame_stats()` to
+    /// stdout and calls `quit()`. Combine with `fixed_frame_delta` and
+    /// `rng_seed` for runs that are comparable with each other.
+    pub benchmark: Option<BenchmarkConfig>,
+    /// Measures how long the early update stages, the game update and the
+    /// late update stages take each frame, see `stage_timings()`.
+    pub profile_stages: bool,
+
+    /// Pauses the game while the window doesn't have focus, the same way
+    /// `pause_engine()` does.
+    pub pause_on_focus_loss: bool,
+    /// Keeps showing what the last `update` drew while the engine is paused,
+    /// instead of only what `paused_update` draws. Whatever `paused_update`
+    /// draws, e.g. a pause menu, ends up on top. Costs a copy of the frame's
+    /// draw calls every frame while enabled.
+    pub freeze_frame_on_pause: bool,
+
+    /// Number of threads in `EngineContext::thread_pool`, `None` uses one
+    /// less than the number of CPUs. Not used on wasm.
+    pub worker_threads: Option<usize>,
+
+    /// Length of a `GameLoop::fixed_update` tick in seconds. Fixed ticks
+    /// follow the measured time, not the framerate, so they run at the same
+    /// rate on a 60Hz and a 144Hz display (including browsers, which run
+    /// frames at the display refresh rate).
+    pub fixed_timestep: f32,
+    /// Upper bound on how many `fixed_update` ticks can run in a single frame.
+    /// Time beyond that is dropped to avoid a spiral of death after a stall.
+    pub max_fixed_steps_per_frame: u32,
+    /// Runs `GameLoop::update` at this rate in Hz instead of once per frame,
+    /// e.g. `Some(20.0)` for a simulation ticking at 20Hz. Frames without an
+    /// update still render and call `GameLoop::render_update`, use
+    /// `update_alpha()` to interpolate between updates. During `update`
+    /// `delta()` is the length of an update. Catching up after a stall is
+    /// capped by `max_fixed_steps_per_frame` as well.
+    pub update_rate: Option<f32>,
+    /// When the just pressed state of buttons is cleared. `paused_update`
+    /// always sees the presses of its frame. With the latching modes they're
+    /// cleared right after it, so presses in a pause menu don't leak into the
+    /// game after unpausing. Latching presses also means
+    /// `render_update` and `update` can see a press on several frames, use
+    /// `consume_just_pressed` to handle it only once.
+    pub just_pressed_mode: JustPressedMode,
+
+    /// Lower bound of the measured frame delta in seconds.
+    pub min_delta: f32,
+    /// Upper bound of the measured frame delta in seconds. Long stalls (e.g.
+    /// dragging the window) are treated as a frame of this length.
+    pub max_delta: f32,
+    /// Instead of discarding the time cut off by `max_delta`, carry it over
+    /// into the following frames (each still at most `max_delta`), so the
+    /// total simulated time matches real time. This trades smoothness for
+    /// simulation fidelity: after a long stall the game runs several
+    /// max-length frames in a row to catch up.
+    pub accumulate_dropped_time: bool,
+
+    /// Delta of the first frame, before there's a measured one. Also feeds
+    /// the `fixed_update` accumulator, so it decides how many fixed ticks run
+    /// on the first frame.
+    pub initial_delta: f32,
+    /// Runs the first frame with a delta of `0.0` and without calling
+    /// `GameLoop::update` (or `fixed_update`), only egui and rendering. The
+    /// frame after it uses `initial_delta` instead of the measured time, so
+    /// heavy loading on the first frame doesn't show up as a jump.
+    pub skip_first_frame_update: bool,
+    /// Forces every frame's delta to this value instead of measuring it,
+    /// which together with `rng_seed` makes runs reproducible for replays.
+    /// Frames still run at the normal framerate.
+    pub fixed_frame_delta: Option<f32>,
+    /// Seed for comfy's global RNG (`rand()`, `gen_range()`, ...). A random
+    /// seed is used when `None`.
+    pub rng_seed: Option<u64>,
+
+    /// Number of frames `frame_stats()` keeps statistics for.
+    pub frame_stats_window: usize,
+
+    /// Time budget of a frame in milliseconds, `None` uses the frame time of
+    /// `target_framerate`.
+    pub frame_budget_ms: Option<f32>,
+    /// Frames taking longer than `frame_budget_ms` times this are logged as
+    /// hitches with a breakdown of where the time went. Set to `0.0` to
+    /// disable the warnings.
+    pub hitch_warning_factor: f32,
+
+    /// Color the frame is cleared to before anything is drawn, visible
+    /// wherever the game doesn't draw. `clear_background` overrides it for a
+    /// single frame.
+    pub clear_color: Color,
+
+    /// Number of samples per pixel for anti-aliasing the scene, `1` turns
+    /// MSAA off. Usually `2`, `4` or `8`. Falls back to the closest count the
+    /// GPU supports, `msaa_samples()` returns the one in use. egui isn't
+    /// affected, it's drawn on top of the resolved frame.
+    pub msaa_samples: u32,
 
     pub bloom_enabled: bool,
     pub tonemapping_enabled: bool,
@@ -89,12 +498,67 @@ pub struct GameConfig {
     pub lighting_enabled: bool,
 
     pub wasm_append_id: Option<String>,
+    /// Stops the browser from handling key and mouse events on the canvas,
+    /// so e.g. F11, Ctrl+S or the scroll wheel only reach the game. Turn it
+    /// off to let the browser shortcuts (fullscreen, zoom, ...) through.
+    pub wasm_prevent_default: bool,
+
+    /// Calls `quit()` when Escape is pressed, with
+    /// `QuitReason::Escape`. Off by default since most games use Escape for
+    /// their menus.
+    pub quit_on_escape: bool,
 
     pub enable_dynamic_camera: bool,
 
     pub dev: DevConfig,
 
     pub scroll_speed: f32,
+    /// How many pixels of a `MouseScrollDelta::PixelDelta` (trackpads, some
+    /// Wayland compositors) count as one line of a regular mouse wheel.
+    pub scroll_pixels_per_line: f32,
+    /// Rate per second at which `mouse_screen_smoothed()` catches up with
+    /// the cursor, e.g. `30.0`. `0.0` turns smoothing off.
+    pub mouse_smoothing: f32,
+
+    /// Maximum time in seconds between two clicks for them to count as a
+    /// double click.
+    pub double_click_time: f32,
+    /// Maximum distance in pixels the cursor can move between clicks of a
+    /// double click.
+    pub double_click_distance: f32,
+    /// Turns the first finger on a touchscreen into the left mouse button,
+    /// so games written for the mouse work on touch devices.
+    pub touch_emulates_mouse: bool,
+    /// Skips new key and mouse presses while egui wants the keyboard or the
+    /// pointer, so that e.g. clicking a button doesn't also click in the
+    /// world. Held state and releases are still tracked.
+    pub block_input_under_ui: bool,
+    /// `trace!`s every keyboard, mouse and resize event with the frame it
+    /// arrived in, before it's applied to the input state.
+    pub log_input_events: bool,
+    /// How many events `recent_input_events()` keeps, `0` turns it off.
+    pub input_event_history: usize,
+    /// Initial action bindings, see `EngineContext::input_map` for changing
+    /// them at runtime.
+    pub input_map: InputMap,
+    /// Deadzone and response curve applied to gamepad axes.
+    pub gamepad: GamepadConfig,
+    /// Lets gamepads navigate egui, moving the focus between widgets and
+    /// clicking them. See `gamepad_ui_nav_mapping` for the buttons used.
+    pub gamepad_ui_nav: bool,
+    pub gamepad_ui_nav_mapping: GamepadUiNavMapping,
+    /// Style applied to egui before the first frame, e.g. to switch to the
+    /// light theme or change font sizes and spacing. `None` keeps egui's
+    /// default dark style. See `set_egui_style` for changing it at runtime.
+    pub egui_style: Option<egui::Style>,
+    /// When `false` egui isn't set up at all: it gets no window state, no
+    /// input and no frames, window events all reach the game and the engine's
+    /// own egui windows are hidden. The game must not draw with `egui()`
+    /// then, egui panics when laying out text outside of a frame.
+    ///
+    /// `draw_text` is drawn with egui and panics as well, text drawn with
+    /// `draw_text_pro_experimental` keeps working.
+    pub enable_egui: bool,
 
     pub music_enabled: bool,
     pub blood_canvas_z: i32,
@@ -103,6 +567,29 @@ pub struct GameConfig {
     pub spawn_exp: bool,
 }
 
+impl GameConfig {
+    /// Value for `target_framerate` that follows the refresh rate of the
+    /// monitor the window is on, see `set_target_framerate_auto`.
+    pub const AUTO_FRAMERATE: u32 = u32::MAX;
+
+    /// The part of a window of `window_size` physical pixels the game is
+    /// rendered into, `None` when it's the whole window, and the integer
+    /// scale it's shown at, see `integer_scaling`.
+    pub fn viewport(&self, window_size: UVec2) -> (Option<Rect>, u32) {
+        let Some(base) = self.integer_scaling else {
+            return (self.resolution.letterbox(window_size), 1);
+        };
+
+        match integer_letterbox(base, window_size) {
+            Some((rect, scale)) => (Some(rect), scale),
+            None => {
+                let fit = ResolutionConfig::Aspect { design: (base.x, base.y) };
+                (fit.letterbox(window_size), 1)
+            }
+        }
+    }
+}
+
 impl Default for GameConfig {
     fn default() -> Self {
         #[cfg(target_arch = "wasm32")]
@@ -121,9 +608,54 @@ impl Default for GameConfig {
 
             resolution,
             min_resolution,
+            max_resolution: None,
+            aspect_ratio: None,
+            integer_scaling: None,
+
+            window_icon: None,
+            window_state: WindowStartState::Normal,
+            decorations: true,
+            cursor_icon: CursorConfig::Default,
 
             target_framerate: 60,
+            loop_mode: LoopMode::Continuous,
+            unfocused_framerate: None,
             vsync_enabled: true,
+            max_frame_latency: None,
+            fifo_disables_frame_limiter: false,
+            pacing: PacingConfig::default(),
+            gpu: GpuConfig::default(),
+            render_during_resize: false,
+            benchmark: None,
+            profile_stages: false,
+
+            pause_on_focus_loss: false,
+            freeze_frame_on_pause: false,
+
+            worker_threads: None,
+
+            fixed_timestep: 1.0 / 60.0,
+            max_fixed_steps_per_frame: 8,
+            update_rate: None,
+            just_pressed_mode: JustPressedMode::PerFrame,
+
+            min_delta: 1.0 / 5000.0,
+            max_delta: 1.0 / 10.0,
+            accumulate_dropped_time: false,
+
+            initial_delta: 1.0 / 60.0,
+            skip_first_frame_update: false,
+            fixed_frame_delta: None,
+            rng_seed: None,
+
+            frame_stats_window: 120,
+
+            frame_budget_ms: None,
+            hitch_warning_factor: 2.0,
+
+            clear_color: BLACK,
+
+            msaa_samples: 1,
 
             bloom_enabled: false,
             tonemapping_enabled: false,
@@ -131,12 +663,29 @@ impl Default for GameConfig {
             lighting_enabled: false,
 
             wasm_append_id: Some("wasm-body".to_string()),
+            wasm_prevent_default: true,
+
+            quit_on_escape: false,
 
             dev: DevConfig::default(),
 
             enable_dynamic_camera: false,
 
             scroll_speed: 7.0,
+            scroll_pixels_per_line: 20.0,
+            mouse_smoothing: 0.0,
+            double_click_time: 0.3,
+            double_click_distance: 5.0,
+            touch_emulates_mouse: false,
+            block_input_under_ui: false,
+            log_input_events: false,
+            input_event_history: 64,
+            input_map: InputMap::new(),
+            gamepad: GamepadConfig::default(),
+            gamepad_ui_nav: false,
+            gamepad_ui_nav_mapping: GamepadUiNavMapping::default(),
+            egui_style: None,
+            enable_egui: true,
             music_enabled: false,
             blood_canvas_z: 4,
 
@@ -0,0 +1,143 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::*;
+
+static ENGINE_COMMANDS: Lazy<Mutex<Vec<EngineCommand>>> =
+    Lazy::new(|| Mutex::new(Vec::new()));
+
+static QUIT_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+static QUIT_REASON: Lazy<Mutex<Option<QuitReason>>> =
+    Lazy::new(|| Mutex::new(None));
+
+/// Why the game is exiting, see `quit_reason()`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum QuitReason {
+    /// `quit()` was called or `quit_flag` was set by the game.
+    Requested,
+    /// The user closed the window.
+    WindowClosed,
+    /// The user pressed Escape with `GameConfig::quit_on_escape` set.
+    Escape,
+}
+
+impl QuitReason {
+    /// Whether the player quit, as opposed to the game quitting on its own.
+    pub fn is_user_initiated(&self) -> bool {
+        !matches!(self, Self::Requested)
+    }
+}
+
+/// Exits the game loop at the start of the next frame. Goes through the same
+/// path as closing the window, so `GameLoop::on_exit` still runs.
+pub fn quit() {
+    quit_with_reason(QuitReason::Requested);
+}
+
+/// Like `quit()`, with the reason `quit_reason()` reports. Only the first
+/// reason is kept.
+pub fn quit_with_reason(reason: QuitReason) {
+    QUIT_REASON.lock().get_or_insert(reason);
+    QUIT_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+/// Why the game is exiting, `None` until it is. Meant for
+/// `GameLoop::on_exit`, e.g. to only show a "saving" message when the player
+/// quit.
+pub fn quit_reason() -> Option<QuitReason> {
+    *QUIT_REASON.lock()
+}
+
+/// Returns `true` once the game is exiting, either through `quit()`,
+/// `quit_flag` or closing the window.
+pub fn should_quit() -> bool {
+    QUIT_REQUESTED.load(Ordering::SeqCst)
+}
+
+/// Requests that need access to the window or the renderer, which are owned by
+/// the event loop. Queue them with `queue_command` and they're applied in
+/// order at the start of the next frame, before `GameLoop::update`.
+///
+/// Most of these have a free function wrapper, e.g. `set_window_title` or
+/// `set_fullscreen`.
+pub enum EngineCommand {
+    SetTitle(String),
+    SetWindowIcon(WindowIcon),
+    SetWindowVisible(bool),
+    SetDecorations(bool),
+    SetResizable(bool),
+    StartWindowDrag,
+    SetCursorVisible(bool),
+    SetCursor(CursorConfig),
+    SetCursorGrab(bool),
+    ConfineCursor(Option<Rect>),
+    SetFullscreen(FullscreenMode),
+    SetPresentMode(PresentMode),
+    Screenshot(ScreenshotRequest),
+    CreateWindow(WindowId, WindowConfig),
+    CloseWindow(WindowId),
+    /// Runs arbitrary code with the window, for anything not covered by the
+    /// other commands. Does nothing when running headless.
+    WithWindow(Box<dyn FnOnce(&Window) + Send>),
+    /// Exits the game loop at the start of the next frame, same as `quit()`.
+    Quit,
+}
+
+impl std::fmt::Debug for EngineCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SetTitle(title) => {
+                f.debug_tuple("SetTitle").field(title).finish()
+            }
+            Self::SetWindowIcon(icon) => {
+                f.debug_tuple("SetWindowIcon").field(icon).finish()
+            }
+            Self::SetWindowVisible(visible) => {
+                f.debug_tuple("SetWindowVisible").field(visible).finish()
+            }
+            Self::SetDecorations(decorations) => {
+                f.debug_tuple("SetDecorations").field(decorations).finish()
+            }
+            Self::SetResizable(resizable) => {
+                f.debug_tuple("SetResizable").field(resizable).finish()
+            }
+            Self::StartWindowDrag => f.write_str("StartWindowDrag"),
+            Self::SetCursorVisible(visible) => {
+                f.debug_tuple("SetCursorVisible").field(visible).finish()
+            }
+            Self::SetCursor(cursor) => {
+                f.debug_tuple("SetCursor").field(cursor).finish()
+            }
+            Self::SetCursorGrab(grab) => {
+                f.debug_tuple("SetCursorGrab").field(grab).finish()
+            }
+            Self::ConfineCursor(rect) => {
+                f.debug_tuple("ConfineCursor").field(rect).finish()
+            }
+            Self::SetFullscreen(mode) => {
+                f.debug_tuple("SetFullscreen").field(mode).finish()
+            }
+            Self::SetPresentMode(mode) => {
+                f.debug_tuple("SetPresentMode").field(mode).finish()
+            }
+            Self::Screenshot(_) => f.write_str("Screenshot"),
+            Self::CreateWindow(id, config) => {
+                f.debug_tuple("CreateWindow").field(id).field(config).finish()
+            }
+            Self::CloseWindow(id) => {
+                f.debug_tuple("CloseWindow").field(id).finish()
+            }
+            Self::WithWindow(_) => f.write_str("WithWindow"),
+            Self::Quit => f.write_str("Quit"),
+        }
+    }
+}
+
+pub fn queue_command(command: EngineCommand) {
+    ENGINE_COMMANDS.lock().push(command);
+}
+
+/// Drains the queued commands. Called by the engine once per frame.
+pub fn take_engine_commands() -> Vec<EngineCommand> {
+    std::mem::take(&mut *ENGINE_COMMANDS.lock())
+}
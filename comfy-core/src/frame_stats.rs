@@ -0,0 +1,249 @@
+use crate::*;
+
+static FRAME_STATS: Lazy<AtomicRefCell<FrameStats>> =
+    Lazy::new(|| AtomicRefCell::new(FrameStats::new(120)));
+
+/// Rolling statistics over the last `game_config().frame_stats_window` frames.
+pub fn frame_stats() -> AtomicRef<'static, FrameStats> {
+    FRAME_STATS.borrow()
+}
+
+pub fn frame_stats_mut() -> AtomicRefMut<'static, FrameStats> {
+    FRAME_STATS.borrow_mut()
+}
+
+/// Ring buffer of recent frame times in seconds. A frame time is the full
+/// duration of a frame including the time spent waiting for the next one.
+pub struct FrameStats {
+    frame_times: VecDeque<f32>,
+    capacity: usize,
+}
+
+impl FrameStats {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+
+        Self { frame_times: VecDeque::with_capacity(capacity), capacity }
+    }
+
+    pub fn push(&mut self, frame_time: f32) {
+        while self.frame_times.len() >= self.capacity {
+            self.frame_times.pop_front();
+        }
+
+        self.frame_times.push_back(frame_time);
+    }
+
+    /// Changes how many frames are kept, dropping the oldest ones if needed.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+
+        while self.frame_times.len() > self.capacity {
+            self.frame_times.pop_front();
+        }
+    }
+
+    pub fn frame_times(&self) -> impl Iterator<Item = f32> + '_ {
+        self.frame_times.iter().copied()
+    }
+
+    /// FPS of the most recent frame.
+    pub fn fps_instant(&self) -> f32 {
+        self.frame_times.back().map_or(0.0, |x| fps_from_frame_time(*x))
+    }
+
+    /// FPS averaged over all frames in the window.
+    pub fn fps_avg(&self) -> f32 {
+        fps_from_frame_time(self.frame_time_avg())
+    }
+
+    pub fn frame_time_avg(&self) -> f32 {
+        if self.frame_times.is_empty() {
+            return 0.0;
+        }
+
+        self.frame_times.iter().sum::<f32>() / self.frame_times.len() as f32
+    }
+
+    /// 99th percentile frame time, handy for spotting hitches that an average
+    /// hides.
+    pub fn frame_time_p99(&self) -> f32 {
+        self.frame_time_percentile(0.99)
+    }
+
+    /// Standard deviation of the frame times, i.e. how much the frames
+    /// deviate from a steady pace. See `GameConfig::pacing` for reducing it.
+    pub fn frame_time_jitter(&self) -> f32 {
+        if self.frame_times.is_empty() {
+            return 0.0;
+        }
+
+        let avg = self.frame_time_avg();
+
+        let variance = self
+            .frame_times
+            .iter()
+            .map(|x| (x - avg) * (x - avg))
+            .sum::<f32>() /
+            self.frame_times.len() as f32;
+
+        variance.sqrt()
+    }
+
+    pub fn frame_time_max(&self) -> f32 {
+        self.frame_times.iter().copied().fold(0.0, f32::max)
+    }
+
+    /// One line summary of the frame times in milliseconds.
+    pub fn report(&self) -> String {
+        format!(
+            "{} frames, avg {:.2}ms ({:.1} fps), p99 {:.2}ms, max {:.2}ms, \
+             jitter {:.2}ms",
+            self.frame_times.len(),
+            self.frame_time_avg() * 1000.0,
+            self.fps_avg(),
+            self.frame_time_p99() * 1000.0,
+            self.frame_time_max() * 1000.0,
+            self.frame_time_jitter() * 1000.0,
+        )
+    }
+
+    /// Frame time at the given percentile in `0.0..=1.0`, using the nearest
+    /// rank.
+    pub fn frame_time_percentile(&self, percentile: f32) -> f32 {
+        if self.frame_times.is_empty() {
+            return 0.0;
+        }
+
+        let mut sorted = self.frame_times.iter().copied().collect_vec();
+        sorted.sort_by(f32::total_cmp);
+
+        let rank = (percentile.clamp(0.0, 1.0) * sorted.len() as f32).ceil();
+        let index = (rank as usize).clamp(1, sorted.len()) - 1;
+
+        sorted[index]
+    }
+}
+
+/// Logs frames that go over the frame budget, see
+/// `GameConfig::hitch_warning_factor`. At most one warning is logged per
+/// second, hitches in between are only counted.
+#[derive(Default)]
+pub struct HitchLogger {
+    last_warning: Option<Instant>,
+    suppressed: u32,
+}
+
+impl HitchLogger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks the full duration of the frame that just ended. The breakdown
+    /// uses the `frame` and `render` timings recorded with
+    /// `span_with_timing!`, whatever is left of the frame is counted as
+    /// sleep.
+    pub fn check(&mut self, frame_time: f32) {
+        let (budget, factor) = {
+            let config = game_config();
+
+            let budget = config
+                .frame_budget_ms
+                .map(|ms| ms / 1000.0)
+                .unwrap_or(1.0 / target_framerate().max(1.0));
+
+            (budget, config.hitch_warning_factor)
+        };
+
+        if factor <= 0.0 || frame_time <= budget * factor {
+            return;
+        }
+
+        let now = Instant::now();
+
+        if self.last_warning.is_some_and(|last| {
+            now.duration_since(last) < Duration::from_secs(1)
+        }) {
+            self.suppressed += 1;
+            return;
+        }
+
+        let (frame, render) = {
+            let timings = timings();
+            let seconds =
+                |name| timings.latest(name).map_or(0.0, |x| x.as_secs_f32());
+
+            (seconds("frame"), seconds("render"))
+        };
+
+        let update = (frame - render).max(0.0);
+        let sleep = (frame_time - frame).max(0.0);
+
+        let suppressed = if self.suppressed > 0 {
+            format!(" ({} more since the last warning)", self.suppressed)
+        } else {
+            String::new()
+        };
+
+        warn!(
+            "Frame took {:.2}ms, over the {:.2}ms budget: update {:.2}ms, \
+             render {:.2}ms, sleep {:.2}ms{}",
+            frame_time * 1000.0,
+            budget * 1000.0,
+            update * 1000.0,
+            render * 1000.0,
+            sleep * 1000.0,
+            suppressed,
+        );
+
+        self.last_warning = Some(now);
+        self.suppressed = 0;
+    }
+}
+
+fn fps_from_frame_time(frame_time: f32) -> f32 {
+    if frame_time > 0.0 {
+        1.0 / frame_time
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_stats_keeps_last_frames() {
+        let mut stats = FrameStats::new(100);
+
+        stats.push(1.0);
+
+        for _ in 0..99 {
+            stats.push(0.01);
+        }
+
+        stats.push(0.5);
+
+        assert_eq!(stats.frame_times().count(), 100);
+        assert_eq!(stats.frame_time_max(), 0.5);
+        assert_eq!(stats.frame_time_p99(), 0.01);
+        assert_eq!(stats.fps_instant(), 2.0);
+    }
+
+    #[test]
+    fn frame_time_jitter_is_zero_for_steady_frames() {
+        let mut stats = FrameStats::new(10);
+
+        for _ in 0..10 {
+            stats.push(0.016);
+        }
+
+        assert!(stats.frame_time_jitter() < 1e-6);
+
+        stats.push(0.026);
+        stats.push(0.006);
+
+        assert!(stats.frame_time_jitter() > 0.004);
+    }
+}
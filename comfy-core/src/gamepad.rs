@@ -0,0 +1,563 @@
+use crate::*;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GamepadId(pub usize);
+
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub enum GamepadButton {
+    /// Bottom face button (A on Xbox, Cross on PlayStation).
+    South,
+    /// Right face button (B on Xbox, Circle on PlayStation).
+    East,
+    /// Top face button (Y on Xbox, Triangle on PlayStation).
+    North,
+    /// Left face button (X on Xbox, Square on PlayStation).
+    West,
+    LeftTrigger,
+    LeftTrigger2,
+    RightTrigger,
+    RightTrigger2,
+    Select,
+    Start,
+    Mode,
+    LeftThumb,
+    RightThumb,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    LeftZ,
+    RightStickX,
+    RightStickY,
+    RightZ,
+    DPadX,
+    DPadY,
+}
+
+impl GamepadAxis {
+    /// The other axis of the same stick, used by `DeadzoneMode::Radial`.
+    pub fn stick_pair(self) -> Option<GamepadAxis> {
+        match self {
+            Self::LeftStickX => Some(Self::LeftStickY),
+            Self::LeftStickY => Some(Self::LeftStickX),
+            Self::RightStickX => Some(Self::RightStickY),
+            Self::RightStickY => Some(Self::RightStickX),
+            _ => None,
+        }
+    }
+}
+
+/// Maps the axis value after the deadzone to the reported value. The sign
+/// is kept, only the magnitude is curved.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum ResponseCurve {
+    #[default]
+    Linear,
+    /// Finer control near the center, same as `Exponent(2.0)`.
+    Squared,
+    Exponent(f32),
+}
+
+impl ResponseCurve {
+    pub fn apply(self, value: f32) -> f32 {
+        let exponent = match self {
+            Self::Linear => return value,
+            Self::Squared => 2.0,
+            Self::Exponent(exponent) => exponent.max(0.01),
+        };
+
+        value.signum() * value.abs().powf(exponent)
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum DeadzoneMode {
+    /// Applies the deadzone to the length of the stick vector, so the
+    /// deadzone is a circle and diagonals behave the same as straight
+    /// directions. Axes that aren't part of a stick fall back to `PerAxis`.
+    #[default]
+    Radial,
+    /// Applies the deadzone to each axis separately. Simple, but it snaps
+    /// the stick to the axes near the center, which feels like a square
+    /// gate.
+    PerAxis,
+}
+
+/// How raw gamepad axis values are processed before they're reported by
+/// `gamepad_axis`, see `GameConfig::gamepad`. The unprocessed values are
+/// available through `gamepad_axis_raw`.
+#[derive(Clone, Debug, Default)]
+pub struct GamepadConfig {
+    /// Values below this magnitude are reported as `0.0`, the range above it
+    /// is rescaled to start at `0.0` so there's no jump at the edge.
+    pub deadzone: f32,
+    /// Overrides `deadzone` for individual axes. With
+    /// `DeadzoneMode::Radial` a stick uses the deadzone of its X axis.
+    pub axis_deadzones: HashMap<GamepadAxis, f32>,
+    pub deadzone_mode: DeadzoneMode,
+    pub curve: ResponseCurve,
+}
+
+impl GamepadConfig {
+    pub fn axis_deadzone(&self, axis: GamepadAxis) -> f32 {
+        self.axis_deadzones.get(&axis).copied().unwrap_or(self.deadzone)
+    }
+
+    fn process(&self, magnitude: f32, deadzone: f32) -> f32 {
+        let deadzone = deadzone.clamp(0.0, 0.99);
+
+        if magnitude <= deadzone {
+            return 0.0;
+        }
+
+        let scaled = ((magnitude - deadzone) / (1.0 - deadzone)).min(1.0);
+
+        self.curve.apply(scaled)
+    }
+
+    /// Processes a single axis value.
+    pub fn process_axis(&self, axis: GamepadAxis, value: f32) -> f32 {
+        value.signum() * self.process(value.abs(), self.axis_deadzone(axis))
+    }
+
+    /// Processes both axes of a stick together using a radial deadzone.
+    pub fn process_stick(&self, x_axis: GamepadAxis, stick: Vec2) -> Vec2 {
+        let length = stick.length();
+
+        if length == 0.0 {
+            return Vec2::ZERO;
+        }
+
+        stick / length * self.process(length, self.axis_deadzone(x_axis))
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct GamepadState {
+    pub name: String,
+
+    pub pressed: HashSet<GamepadButton>,
+    pub just_pressed: HashSet<GamepadButton>,
+    pub just_released: HashSet<GamepadButton>,
+
+    /// Axis values after the deadzone and response curve.
+    pub axes: HashMap<GamepadAxis, f32>,
+    /// Axis values as reported by the device.
+    pub raw_axes: HashMap<GamepadAxis, f32>,
+}
+
+impl GamepadState {
+    /// Stores a new raw axis value and updates the processed values it
+    /// affects.
+    pub fn set_axis(
+        &mut self,
+        axis: GamepadAxis,
+        value: f32,
+        config: &GamepadConfig,
+    ) {
+        self.raw_axes.insert(axis, value);
+
+        let raw = |axis| self.raw_axes.get(&axis).copied().unwrap_or(0.0);
+
+        match axis.stick_pair() {
+            Some(pair) if config.deadzone_mode == DeadzoneMode::Radial => {
+                let (x_axis, y_axis) = match axis {
+                    GamepadAxis::LeftStickY | GamepadAxis::RightStickY => {
+                        (pair, axis)
+                    }
+                    _ => (axis, pair),
+                };
+
+                let stick = config
+                    .process_stick(x_axis, vec2(raw(x_axis), raw(y_axis)));
+
+                self.axes.insert(x_axis, stick.x);
+                self.axes.insert(y_axis, stick.y);
+            }
+            _ => {
+                self.axes.insert(axis, config.process_axis(axis, value));
+            }
+        }
+    }
+}
+
+/// Returns the ids of all currently connected gamepads.
+pub fn connected_gamepads() -> Vec<GamepadId> {
+    GLOBAL_STATE.borrow().gamepads.keys().copied().sorted().collect_vec()
+}
+
+pub fn is_gamepad_connected(id: GamepadId) -> bool {
+    GLOBAL_STATE.borrow().gamepads.contains_key(&id)
+}
+
+/// Returns the name the OS reported for the gamepad.
+pub fn gamepad_name(id: GamepadId) -> Option<String> {
+    GLOBAL_STATE.borrow().gamepads.get(&id).map(|x| x.name.clone())
+}
+
+pub fn gamepad_button_down(id: GamepadId, button: GamepadButton) -> bool {
+    GLOBAL_STATE
+        .borrow()
+        .gamepads
+        .get(&id)
+        .is_some_and(|x| x.pressed.contains(&button))
+}
+
+pub fn gamepad_button_pressed(id: GamepadId, button: GamepadButton) -> bool {
+    GLOBAL_STATE
+        .borrow()
+        .gamepads
+        .get(&id)
+        .is_some_and(|x| x.just_pressed.contains(&button))
+}
+
+pub fn gamepad_button_released(id: GamepadId, button: GamepadButton) -> bool {
+    GLOBAL_STATE
+        .borrow()
+        .gamepads
+        .get(&id)
+        .is_some_and(|x| x.just_released.contains(&button))
+}
+
+/// Returns the axis value in `-1.0..=1.0` after the deadzone and response
+/// curve from `GameConfig::gamepad`, or `0.0` if the gamepad isn't connected.
+pub fn gamepad_axis(id: GamepadId, axis: GamepadAxis) -> f32 {
+    GLOBAL_STATE
+        .borrow()
+        .gamepads
+        .get(&id)
+        .and_then(|x| x.axes.get(&axis).copied())
+        .unwrap_or(0.0)
+}
+
+/// Like `gamepad_axis`, but without any deadzone or response curve applied.
+pub fn gamepad_axis_raw(id: GamepadId, axis: GamepadAxis) -> f32 {
+    GLOBAL_STATE
+        .borrow()
+        .gamepads
+        .get(&id)
+        .and_then(|x| x.raw_axes.get(&axis).copied())
+        .unwrap_or(0.0)
+}
+
+/// Which egui keys the gamepad buttons act as when
+/// `GameConfig::gamepad_ui_nav` is enabled. By default the face buttons
+/// activate (`Enter`) and go back (`Escape`), the d-pad and left stick
+/// move the focus like the arrow keys and the right bumper cycles through
+/// the widgets like `Tab`. egui only moves the focus with the arrow keys once
+/// a widget has focus.
+#[derive(Clone, Debug)]
+pub struct GamepadUiNavMapping {
+    pub buttons: Vec<(GamepadButton, egui::Key)>,
+    /// Moves the focus with the left stick once it's pushed this far, `None`
+    /// disables the stick.
+    pub stick_threshold: Option<f32>,
+}
+
+impl Default for GamepadUiNavMapping {
+    fn default() -> Self {
+        Self {
+            buttons: vec![
+                (GamepadButton::South, egui::Key::Enter),
+                (GamepadButton::East, egui::Key::Escape),
+                (GamepadButton::DPadUp, egui::Key::ArrowUp),
+                (GamepadButton::DPadDown, egui::Key::ArrowDown),
+                (GamepadButton::DPadLeft, egui::Key::ArrowLeft),
+                (GamepadButton::DPadRight, egui::Key::ArrowRight),
+                (GamepadButton::RightTrigger, egui::Key::Tab),
+            ],
+            stick_threshold: Some(0.5),
+        }
+    }
+}
+
+/// Turns gamepad input into egui key events for navigating the UI.
+#[derive(Default)]
+pub struct GamepadUiNav {
+    /// Direction the stick is held in, so it only moves the focus once per
+    /// push.
+    stick_key: Option<egui::Key>,
+}
+
+impl GamepadUiNav {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Synthetic egui events for the gamepad input of the current frame.
+    pub fn events(
+        &mut self,
+        mapping: &GamepadUiNavMapping,
+    ) -> Vec<egui::Event> {
+        let state = GLOBAL_STATE.borrow();
+
+        let mut keys = Vec::new();
+        let mut stick = Vec2::ZERO;
+
+        for gamepad in state.gamepads.values() {
+            for (button, key) in mapping.buttons.iter() {
+                if gamepad.just_pressed.contains(button) {
+                    keys.push(*key);
+                }
+            }
+
+            let axis = |axis| gamepad.axes.get(&axis).copied().unwrap_or(0.0);
+            let value = vec2(
+                axis(GamepadAxis::LeftStickX),
+                axis(GamepadAxis::LeftStickY),
+            );
+
+            if value.length_squared() > stick.length_squared() {
+                stick = value;
+            }
+        }
+
+        let stick_key = mapping.stick_threshold.and_then(|threshold| {
+            if stick.length() < threshold {
+                None
+            } else if stick.x.abs() > stick.y.abs() {
+                Some(
+                    if stick.x > 0.0 {
+                        egui::Key::ArrowRight
+                    } else {
+                        egui::Key::ArrowLeft
+                    },
+                )
+            } else {
+                Some(
+                    if stick.y > 0.0 {
+                        egui::Key::ArrowUp
+                    } else {
+                        egui::Key::ArrowDown
+                    },
+                )
+            }
+        });
+
+        if stick_key != self.stick_key {
+            keys.extend(stick_key);
+            self.stick_key = stick_key;
+        }
+
+        keys.into_iter()
+            .flat_map(|key| {
+                [true, false].map(|pressed| {
+                    egui::Event::Key {
+                        key,
+                        pressed,
+                        repeat: false,
+                        modifiers: egui::Modifiers::NONE,
+                    }
+                })
+            })
+            .collect()
+    }
+}
+
+/// Polls gamepad events and feeds them into `GLOBAL_STATE`.
+///
+/// Without the `gamepad` feature this does nothing and no gamepads are ever
+/// reported as connected.
+pub struct GamepadPoller {
+    #[cfg(feature = "gamepad")]
+    gilrs: Option<gilrs::Gilrs>,
+}
+
+impl GamepadPoller {
+    pub fn new() -> Self {
+        cfg_if! {
+            if #[cfg(feature = "gamepad")] {
+                let gilrs = match gilrs::Gilrs::new() {
+                    Ok(gilrs) => Some(gilrs),
+                    Err(err) => {
+                        error!("Failed to initialize gamepad support: {}", err);
+                        None
+                    }
+                };
+
+                if let Some(gilrs) = gilrs.as_ref() {
+                    let mut state = GLOBAL_STATE.borrow_mut();
+
+                    for (id, gamepad) in gilrs.gamepads() {
+                        state.gamepads.insert(
+                            GamepadId(id.into()),
+                            GamepadState {
+                                name: gamepad.name().to_string(),
+                                ..Default::default()
+                            },
+                        );
+                    }
+                }
+
+                Self { gilrs }
+            } else {
+                Self {}
+            }
+        }
+    }
+
+    pub fn poll(&mut self) {
+        #[cfg(feature = "gamepad")]
+        if let Some(gilrs) = self.gilrs.as_mut() {
+            let config = game_config().gamepad.clone();
+
+            while let Some(gilrs::Event { id, event, .. }) = gilrs.next_event()
+            {
+                let name = gilrs.gamepad(id).name().to_string();
+                let id = GamepadId(id.into());
+
+                let mut state = GLOBAL_STATE.borrow_mut();
+
+                match event {
+                    gilrs::EventType::Connected => {
+                        info!("Gamepad connected: {} ({:?})", name, id);
+
+                        state.gamepads.insert(id, GamepadState {
+                            name,
+                            ..Default::default()
+                        });
+                    }
+
+                    gilrs::EventType::Disconnected => {
+                        info!("Gamepad disconnected: {} ({:?})", name, id);
+                        state.gamepads.remove(&id);
+                    }
+
+                    gilrs::EventType::ButtonPressed(button, _) => {
+                        if let Some(button) = map_gilrs_button(button) {
+                            let gamepad = state.gamepads.entry(id).or_default();
+
+                            gamepad.pressed.insert(button);
+                            gamepad.just_pressed.insert(button);
+                            gamepad.just_released.remove(&button);
+                        }
+                    }
+
+                    gilrs::EventType::ButtonReleased(button, _) => {
+                        if let Some(button) = map_gilrs_button(button) {
+                            let gamepad = state.gamepads.entry(id).or_default();
+
+                            gamepad.pressed.remove(&button);
+                            gamepad.just_pressed.remove(&button);
+                            gamepad.just_released.insert(button);
+                        }
+                    }
+
+                    gilrs::EventType::AxisChanged(axis, value, _) => {
+                        if let Some(axis) = map_gilrs_axis(axis) {
+                            state
+                                .gamepads
+                                .entry(id)
+                                .or_default()
+                                .set_axis(axis, value, &config);
+                        }
+                    }
+
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+impl Default for GamepadPoller {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "gamepad")]
+fn map_gilrs_button(button: gilrs::Button) -> Option<GamepadButton> {
+    use gilrs::Button;
+
+    Some(match button {
+        Button::South => GamepadButton::South,
+        Button::East => GamepadButton::East,
+        Button::North => GamepadButton::North,
+        Button::West => GamepadButton::West,
+        Button::LeftTrigger => GamepadButton::LeftTrigger,
+        Button::LeftTrigger2 => GamepadButton::LeftTrigger2,
+        Button::RightTrigger => GamepadButton::RightTrigger,
+        Button::RightTrigger2 => GamepadButton::RightTrigger2,
+        Button::Select => GamepadButton::Select,
+        Button::Start => GamepadButton::Start,
+        Button::Mode => GamepadButton::Mode,
+        Button::LeftThumb => GamepadButton::LeftThumb,
+        Button::RightThumb => GamepadButton::RightThumb,
+        Button::DPadUp => GamepadButton::DPadUp,
+        Button::DPadDown => GamepadButton::DPadDown,
+        Button::DPadLeft => GamepadButton::DPadLeft,
+        Button::DPadRight => GamepadButton::DPadRight,
+        _ => return None,
+    })
+}
+
+#[cfg(feature = "gamepad")]
+fn map_gilrs_axis(axis: gilrs::Axis) -> Option<GamepadAxis> {
+    use gilrs::Axis;
+
+    Some(match axis {
+        Axis::LeftStickX => GamepadAxis::LeftStickX,
+        Axis::LeftStickY => GamepadAxis::LeftStickY,
+        Axis::LeftZ => GamepadAxis::LeftZ,
+        Axis::RightStickX => GamepadAxis::RightStickX,
+        Axis::RightStickY => GamepadAxis::RightStickY,
+        Axis::RightZ => GamepadAxis::RightZ,
+        Axis::DPadX => GamepadAxis::DPadX,
+        Axis::DPadY => GamepadAxis::DPadY,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn radial_deadzone_keeps_stick_direction() {
+        let config = GamepadConfig { deadzone: 0.2, ..Default::default() };
+
+        let mut state = GamepadState::default();
+        state.set_axis(GamepadAxis::LeftStickX, 0.15, &config);
+        state.set_axis(GamepadAxis::LeftStickY, 0.15, &config);
+
+        // Each axis is inside the deadzone, but the stick as a whole isn't.
+        let x = state.axes[&GamepadAxis::LeftStickX];
+        let y = state.axes[&GamepadAxis::LeftStickY];
+
+        assert!(x > 0.0);
+        assert!((x - y).abs() < 1e-6);
+
+        let per_axis =
+            GamepadConfig { deadzone_mode: DeadzoneMode::PerAxis, ..config };
+
+        state.set_axis(GamepadAxis::LeftStickX, 0.15, &per_axis);
+
+        assert_eq!(state.axes[&GamepadAxis::LeftStickX], 0.0);
+        assert_eq!(state.raw_axes[&GamepadAxis::LeftStickX], 0.15);
+    }
+}
@@ -1,4 +1,6 @@
-use std::sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::atomic::{
+    AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering,
+};
 
 use crate::*;
 
@@ -8,20 +10,82 @@ pub fn egui() -> &'static egui::Context {
     &EGUI_CONTEXT
 }
 
+static EGUI_OUTPUT: Lazy<Mutex<egui::PlatformOutput>> =
+    Lazy::new(|| Mutex::new(egui::PlatformOutput::default()));
+
+/// What egui asked of the platform on the last frame, e.g. the cursor icon
+/// it wants, a URL to open or copied text. The engine already applies it to
+/// the main window, this is for games that want to react to it as well.
+pub fn egui_output() -> egui::PlatformOutput {
+    EGUI_OUTPUT.lock().clone()
+}
+
+/// Called by the renderer at the end of every egui frame.
+pub fn set_egui_output(output: egui::PlatformOutput) {
+    *EGUI_OUTPUT.lock() = output;
+}
+
+/// Replaces the egui style, takes effect on the next frame. Use
+/// `GameConfig::egui_style` to set it before the first frame.
+pub fn set_egui_style(style: egui::Style) {
+    egui().set_style(style);
+}
+
+/// Switches egui between its default dark and light visuals, keeping the
+/// rest of the style. For the UI scale see `set_ui_scale`.
+pub fn set_egui_dark_mode(dark: bool) {
+    let visuals =
+        if dark { egui::Visuals::dark() } else { egui::Visuals::light() };
+
+    egui().set_visuals(visuals);
+}
+
+pub fn is_egui_dark_mode() -> bool {
+    egui().style().visuals.dark_mode
+}
+
 static FRAME_TIME: AtomicU32 =
     AtomicU32::new(unsafe { std::mem::transmute(1.0f32) });
 
 static DELTA: AtomicU32 =
     AtomicU32::new(unsafe { std::mem::transmute(1f32 / 60f32) });
 
+static DELTA_F64: AtomicU64 = AtomicU64::new((1f64 / 60f64).to_bits());
+
+static REAL_DELTA: AtomicU32 =
+    AtomicU32::new(unsafe { std::mem::transmute(1f32 / 60f32) });
+
+static FIXED_DELTA: AtomicU32 =
+    AtomicU32::new(unsafe { std::mem::transmute(1f32 / 60f32) });
+
+static FIXED_ALPHA: AtomicU32 =
+    AtomicU32::new(unsafe { std::mem::transmute(0.0f32) });
+
+static UPDATE_ALPHA: AtomicU32 = AtomicU32::new(1.0f32.to_bits());
+
 static TIME_SCALE: AtomicU32 =
     AtomicU32::new(unsafe { std::mem::transmute(1.0f32) });
 
+static TARGET_FRAMERATE: AtomicU32 = AtomicU32::new(60.0f32.to_bits());
+static TARGET_FRAMERATE_AUTO: AtomicBool = AtomicBool::new(false);
+
 static TIME: AtomicU64 = AtomicU64::new(unsafe { std::mem::transmute(0.0f64) });
 
 static UNPAUSED_TIME: AtomicU64 =
     AtomicU64::new(unsafe { std::mem::transmute(0.0f64) });
 
+static REAL_TIME_START: OnceCell<Instant> = OnceCell::new();
+
+// Bits of an `f64`, `u64::MAX` (a NaN) while following the wall clock.
+static SIMULATED_REAL_TIME: AtomicU64 = AtomicU64::new(u64::MAX);
+
+static FRAME_COUNT: AtomicU64 = AtomicU64::new(0);
+
+// Bits of an `f64`, zero is `0.0`.
+static DROPPED_TIME: AtomicU64 = AtomicU64::new(0);
+
+static ENGINE_PAUSED: AtomicBool = AtomicBool::new(false);
+
 static ASSETS_QUEUED: AtomicUsize = AtomicUsize::new(0);
 static ASSETS_LOADED: AtomicUsize = AtomicUsize::new(0);
 
@@ -50,6 +114,22 @@ pub fn inc_assets_loaded(newly_loaded_count: usize) {
 }
 
 
+/// Pauses the simulation. While paused the game loop stops calling
+/// `GameLoop::update` and `GameLoop::fixed_update`, delta is forced to `0.0`,
+/// but rendering, egui and input keep working so menus can still be used.
+pub fn pause_engine() {
+    ENGINE_PAUSED.store(true, Ordering::SeqCst);
+}
+
+/// Resumes the simulation after `pause_engine()`.
+pub fn resume_engine() {
+    ENGINE_PAUSED.store(false, Ordering::SeqCst);
+}
+
+pub fn is_engine_paused() -> bool {
+    ENGINE_PAUSED.load(Ordering::SeqCst)
+}
+
 pub fn frame_time() -> f32 {
     f32::from_bits(FRAME_TIME.load(Ordering::SeqCst))
 }
@@ -58,24 +138,134 @@ pub fn set_frame_time(value: f32) {
     FRAME_TIME.store(value.to_bits(), Ordering::SeqCst);
 }
 
+/// Multiplier applied to the per-frame delta and to the game clock
+/// (`get_time()`). Use `real_delta()` for timing that shouldn't be affected.
 pub fn time_scale() -> f32 {
     f32::from_bits(TIME_SCALE.load(Ordering::SeqCst))
 }
 
+/// Frames per second the frame limiter aims for, `0.0` if it's uncapped.
+/// Starts out as `GameConfig::target_framerate`.
+pub fn target_framerate() -> f32 {
+    f32::from_bits(TARGET_FRAMERATE.load(Ordering::SeqCst))
+}
+
+/// Changes the framerate limit from the next frame on, e.g. from a settings
+/// menu. `0.0` (or any non-positive value) removes the limit. On wasm the
+/// browser decides when frames run, so the limit skips browser frames and
+/// divides the display refresh rate, e.g. 30 FPS runs every other frame on
+/// a 60Hz display.
+pub fn set_target_framerate(fps: f32) {
+    TARGET_FRAMERATE_AUTO.store(false, Ordering::SeqCst);
+    TARGET_FRAMERATE.store(fps.max(0.0).to_bits(), Ordering::SeqCst);
+}
+
+/// Makes the frame limiter follow the refresh rate of the monitor the window
+/// is on, detected again whenever the window moves. Falls back to 60 FPS
+/// when the rate can't be detected. `set_target_framerate` switches back to
+/// a fixed limit.
+pub fn set_target_framerate_auto() {
+    TARGET_FRAMERATE_AUTO.store(true, Ordering::SeqCst);
+}
+
+/// Whether the framerate limit follows the monitor, see
+/// `set_target_framerate_auto`.
+pub fn is_target_framerate_auto() -> bool {
+    TARGET_FRAMERATE_AUTO.load(Ordering::SeqCst)
+}
+
+/// Applies a detected monitor refresh rate while the framerate limit is in
+/// auto mode. Called by the engine.
+pub fn set_detected_refresh_rate(rate: Option<f32>) {
+    if is_target_framerate_auto() {
+        let fps = rate.filter(|x| *x > 0.0).unwrap_or(60.0);
+        TARGET_FRAMERATE.store(fps.to_bits(), Ordering::SeqCst);
+    }
+}
+
+/// Sets the time scale. `0.0` freezes gameplay time completely, negative
+/// values are clamped to `0.0`.
 pub fn set_time_scale(value: f32) {
-    TIME_SCALE.store(value.to_bits(), Ordering::SeqCst);
+    TIME_SCALE.store(value.max(0.0).to_bits(), Ordering::SeqCst);
 }
 
+/// Time elapsed since the last frame in seconds, scaled by `time_scale()`.
 pub fn delta() -> f32 {
     // TODO: remove !!!!
     span_with_timing!("delta");
-    f32::from_bits(DELTA.load(Ordering::SeqCst)) * time_scale()
+    f32::from_bits(DELTA.load(Ordering::SeqCst))
 }
 
 pub fn set_delta(value: f32) {
     DELTA.store(value.to_bits(), Ordering::SeqCst);
 }
 
+/// `delta()` at full precision, measured as an `f64` from the start. This is
+/// what `get_time()` advances by, prefer it when accumulating time over long
+/// sessions.
+pub fn delta_f64() -> f64 {
+    f64::from_bits(DELTA_F64.load(Ordering::SeqCst))
+}
+
+pub fn set_delta_f64(value: f64) {
+    DELTA_F64.store(value.to_bits(), Ordering::SeqCst);
+}
+
+/// Sets `delta()` and `delta_f64()` for the frame and advances `get_time()`
+/// by it. Called by the engine once per frame with the scaled delta.
+pub fn advance_frame_time(delta: f64) {
+    set_delta(delta as f32);
+    set_delta_f64(delta);
+    set_time(next_frame_time(get_time(), delta));
+}
+
+/// The clock is kept as `f64`, adding `f32` deltas to an `f32` time stops
+/// being precise after a few minutes.
+fn next_frame_time(time: f64, delta: f64) -> f64 {
+    time + delta
+}
+
+/// Unscaled time elapsed since the last frame in seconds. Unlike `delta()`
+/// this ignores `time_scale()` and keeps advancing while the engine is
+/// paused, which makes it a good fit for UI animations.
+pub fn real_delta() -> f32 {
+    f32::from_bits(REAL_DELTA.load(Ordering::SeqCst))
+}
+
+pub fn set_real_delta(value: f32) {
+    REAL_DELTA.store(value.to_bits(), Ordering::SeqCst);
+}
+
+/// Length of a single `fixed_update` tick in seconds.
+pub fn fixed_delta() -> f32 {
+    f32::from_bits(FIXED_DELTA.load(Ordering::SeqCst))
+}
+
+pub fn set_fixed_delta(value: f32) {
+    FIXED_DELTA.store(value.to_bits(), Ordering::SeqCst);
+}
+
+/// How far (in `0.0..1.0`) the current frame is between the last and the next
+/// `fixed_update` tick. Useful for interpolating positions when rendering.
+pub fn fixed_alpha() -> f32 {
+    f32::from_bits(FIXED_ALPHA.load(Ordering::SeqCst))
+}
+
+pub fn set_fixed_alpha(value: f32) {
+    FIXED_ALPHA.store(value.to_bits(), Ordering::SeqCst);
+}
+
+/// Like `fixed_alpha()`, but for `GameConfig::update_rate`: how far the
+/// current frame is between the last and the next `update`. Always `1.0`
+/// when `update` runs every frame.
+pub fn update_alpha() -> f32 {
+    f32::from_bits(UPDATE_ALPHA.load(Ordering::SeqCst))
+}
+
+pub fn set_update_alpha(value: f32) {
+    UPDATE_ALPHA.store(value.to_bits(), Ordering::SeqCst);
+}
+
 pub fn get_time() -> f64 {
     f64::from_bits(TIME.load(Ordering::SeqCst))
 }
@@ -84,6 +274,79 @@ pub fn set_time(value: f64) {
     TIME.store(value.to_bits(), Ordering::SeqCst);
 }
 
+/// Wall-clock seconds since the game loop started. Unlike `get_time()` this
+/// isn't affected by `time_scale()`, pausing or delta clamping, which makes it
+/// useful for shader animations and UI that should keep moving while the game
+/// is paused.
+pub fn real_time() -> f64 {
+    let simulated = SIMULATED_REAL_TIME.load(Ordering::SeqCst);
+
+    if simulated != u64::MAX {
+        return f64::from_bits(simulated);
+    }
+
+    REAL_TIME_START.get().map_or(0.0, |start| start.elapsed().as_secs_f64())
+}
+
+/// Makes `real_time()` return `time` instead of reading the wall clock, or
+/// go back to the wall clock with `None`. Used by `TestHarness` so timings
+/// such as `key_held_time` follow the simulated frames.
+pub fn set_simulated_real_time(time: Option<f64>) {
+    SIMULATED_REAL_TIME
+        .store(time.map_or(u64::MAX, f64::to_bits), Ordering::SeqCst);
+}
+
+/// Marks the point `real_time()` is measured from. Called by the engine right
+/// before the game loop starts, later calls do nothing.
+pub fn start_real_time() {
+    REAL_TIME_START.get_or_init(Instant::now);
+}
+
+/// Milliseconds from the browser's `performance.now()`, which is what the
+/// game loop measures frame times with on wasm.
+#[cfg(target_arch = "wasm32")]
+pub fn performance_now() -> f64 {
+    web_sys::window()
+        .and_then(|window| window.performance())
+        .map_or(0.0, |performance| performance.now())
+}
+
+/// Number of frames the engine has started so far, the same value as
+/// `EngineContext::frame`.
+pub fn frame_count() -> u64 {
+    FRAME_COUNT.load(Ordering::SeqCst)
+}
+
+pub fn set_frame_count(value: u64) {
+    FRAME_COUNT.store(value, Ordering::SeqCst);
+}
+
+/// Returns `true` every `n` frames, e.g. `on_every_n_frames(30, 0)` for
+/// throttling work to twice a second at 60 FPS. `offset` shifts which frames
+/// match, so different systems can spread their work over separate frames.
+/// Based on `frame_count()`, which keeps counting while paused. Always
+/// `false` for `n == 0`.
+pub fn on_every_n_frames(n: u64, offset: u64) -> bool {
+    n > 0 && frame_count() % n == offset % n
+}
+
+/// Seconds of game time since the start, i.e. `get_time()` as an `f32`.
+/// Stops while paused and follows `time_scale()`, see `real_time()` for the
+/// wall clock.
+pub fn seconds_elapsed() -> f32 {
+    get_time() as f32
+}
+
+/// Total seconds of frame time discarded by the `max_delta` clamp since the
+/// game started. Stays at zero with `GameConfig::accumulate_dropped_time`.
+pub fn dropped_time() -> f64 {
+    f64::from_bits(DROPPED_TIME.load(Ordering::SeqCst))
+}
+
+pub fn add_dropped_time(value: f64) {
+    DROPPED_TIME.store((dropped_time() + value).to_bits(), Ordering::SeqCst);
+}
+
 pub fn get_unpaused_time() -> f64 {
     f64::from_bits(UNPAUSED_TIME.load(Ordering::SeqCst))
 }
@@ -98,32 +361,114 @@ pub static GLOBAL_STATE: Lazy<AtomicRefCell<GlobalState>> =
 #[derive(Default)]
 pub struct GlobalState {
     pub mouse_wheel: (f32, f32),
+    pub mouse_delta: Vec2,
     pub mouse_position: Vec2,
+    /// See `mouse_screen_smoothed()`.
+    pub mouse_position_smoothed: Vec2,
+    /// See `mouse_moved_this_frame()`.
+    pub mouse_moved: bool,
     pub mouse_rel: IVec2,
     pub mouse_world: Vec2,
 
     pub mouse_locked: bool,
 
+    pub window_focused: bool,
+    pub window_minimized: bool,
+    /// Physical inner size of the window as of the last resize.
+    pub window_size: UVec2,
+    /// See `window_geometry()`.
+    pub window_geometry: WindowGeometry,
+    /// See `safe_area_insets()`.
+    pub safe_area_insets: (f32, f32, f32, f32),
+
+    /// Cursor set by the game, restored after egui showed its own.
+    pub cursor: CursorConfig,
+    pub cursor_hidden: bool,
+    pub cursor_grab_mode: CursorGrabMode,
+    /// See `confine_cursor_to_rect`.
+    pub cursor_confine_rect: Option<Rect>,
+    pub present_mode: Option<PresentMode>,
+
     pub egui_scale_factor: f32,
+    /// OS scale factor of the window, see `mouse_position_logical`.
+    pub window_scale_factor: f32,
 
     pub frame: u32,
     pub fps: i32,
 
     pub mesh_queue: Vec<MeshDraw>,
     pub text_queue: Vec<DrawText>,
+    /// What the game drew in its last update before it was paused, see
+    /// `GameConfig::freeze_frame_on_pause`.
+    pub frozen_mesh_queue: Vec<MeshDraw>,
+    pub frozen_text_queue: Vec<DrawText>,
 
     pub clear_color: Color,
 
     pub screen_size: Vec2,
+    /// Letterboxed area of the window, see `viewport_rect()`.
+    pub viewport: Option<Rect>,
+    /// See `pixel_scale()`.
+    pub pixel_scale: u32,
 
     pub pressed: HashSet<KeyCode>,
     pub just_pressed: HashSet<KeyCode>,
     pub just_released: HashSet<KeyCode>,
+    /// Keys pressed during the current frame, cleared every frame regardless
+    /// of `GameConfig::just_pressed_mode`.
+    pub frame_pressed: HashSet<KeyCode>,
+    pub key_press_times: HashMap<KeyCode, f64>,
+
+    /// Physical keys, tracked alongside the `KeyCode`s above, see
+    /// `ScanCode`.
+    pub pressed_scancodes: HashSet<ScanCode>,
+    pub just_pressed_scancodes: HashSet<ScanCode>,
+    pub just_released_scancodes: HashSet<ScanCode>,
+
+    pub text_input: String,
 
     pub mouse_pressed: HashSet<MouseButton>,
     pub mouse_just_pressed: HashSet<MouseButton>,
     pub mouse_just_released: HashSet<MouseButton>,
+    pub mouse_clicks: HashMap<MouseButton, MouseClick>,
+
+    pub gamepads: HashMap<GamepadId, GamepadState>,
+
+    pub touches: HashMap<u64, TouchPoint>,
+    /// Touch that drives the emulated left mouse button, see
+    /// `GameConfig::touch_emulates_mouse`.
+    pub primary_touch: Option<u64>,
+
+    pub dropped_files: Vec<PathBuf>,
+    pub hovered_files: HashSet<PathBuf>,
 
     pub play_sound_queue: Vec<Sound>,
     pub stop_sound_queue: Vec<Sound>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_time_accumulates_without_f32_drift() {
+        // 60 fps for a bit over four and a half hours.
+        let frames = 1_000_000u64;
+        let frame = Duration::from_nanos(16_666_667);
+
+        let mut time = 0.0;
+        let mut truncated = 0.0f64;
+
+        for _ in 0..frames {
+            time = next_frame_time(time, frame.as_secs_f64());
+            truncated += frame.as_secs_f32() as f64;
+        }
+
+        let exact = (frame * frames as u32).as_secs_f64();
+        let error = (time - exact).abs();
+        let truncated_error = (truncated - exact).abs();
+
+        assert!(error < 1e-6, "f64 clock drifted by {}s", error);
+        assert!(error < truncated_error);
+    }
+}
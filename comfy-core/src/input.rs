@@ -4,6 +4,86 @@ pub fn mouse_wheel() -> (f32, f32) {
     GLOBAL_STATE.borrow().mouse_wheel
 }
 
+/// Raw relative mouse motion accumulated over the current frame.
+///
+/// The values come straight from the device and are in unspecified units
+/// (not pixels), and unlike `mouse_screen()` they keep updating while the
+/// cursor is grabbed or pinned to the window edge. Useful for FPS-style
+/// camera control.
+pub fn mouse_delta() -> Vec2 {
+    GLOBAL_STATE.borrow().mouse_delta
+}
+
+/// Whether the cursor moved since the last frame, either through a
+/// `CursorMoved` event or an emulated touch. Unlike `mouse_delta()` this
+/// stays `false` for raw device motion while the cursor is grabbed.
+pub fn mouse_moved_this_frame() -> bool {
+    GLOBAL_STATE.borrow().mouse_moved
+}
+
+/// `mouse_screen()` eased toward the newest cursor position once per frame
+/// at `GameConfig::mouse_smoothing`. Same as `mouse_screen()` when smoothing
+/// is off. Meant for cursors and UI drawn at a higher rate than input
+/// arrives, game logic should keep using the exact position.
+pub fn mouse_screen_smoothed() -> Vec2 {
+    GLOBAL_STATE.borrow().mouse_position_smoothed
+}
+
+/// Mouse position in logical pixels, i.e. `mouse_screen()` (which is in
+/// physical pixels) divided by the window scale factor. Use this when laying
+/// out UI in logical units on HiDPI displays.
+pub fn mouse_position_logical() -> Vec2 {
+    let state = GLOBAL_STATE.borrow();
+    to_logical(state.mouse_position, state.window_scale_factor)
+}
+
+/// Divides a physical position by the scale factor, treating a scale factor
+/// that wasn't set yet as `1.0`.
+fn to_logical(position: Vec2, scale_factor: f32) -> Vec2 {
+    let scale_factor = if scale_factor > 0.0 { scale_factor } else { 1.0 };
+
+    position / scale_factor
+}
+
+/// Returns the text typed since the last call, draining the buffer.
+///
+/// Unlike `is_key_pressed` this respects the keyboard layout, shift and dead
+/// keys, and includes text committed by an IME. Control characters such as
+/// backspace or enter are filtered out, use the keycode API for those.
+/// Text typed into an egui widget isn't reported here.
+pub fn take_text_input() -> String {
+    std::mem::take(&mut GLOBAL_STATE.borrow_mut().text_input)
+}
+
+/// Returns files dropped onto the window since the last call, draining the
+/// list. Files are never dropped on wasm, so this always returns an empty vec
+/// there.
+pub fn take_dropped_files() -> Vec<PathBuf> {
+    std::mem::take(&mut GLOBAL_STATE.borrow_mut().dropped_files)
+}
+
+/// Files currently being dragged over the window. Cleared when the drag is
+/// cancelled or the files are dropped. Always empty on wasm.
+pub fn hovered_files() -> Vec<PathBuf> {
+    GLOBAL_STATE.borrow().hovered_files.iter().cloned().collect_vec()
+}
+
+/// Converts a winit scroll delta into the line based `(x, y)` representation
+/// used by `mouse_wheel()`. Pixel deltas are divided by `pixels_per_line`.
+pub fn scroll_delta_to_lines(
+    delta: &MouseScrollDelta,
+    pixels_per_line: f32,
+) -> (f32, f32) {
+    match delta {
+        MouseScrollDelta::LineDelta(x, y) => (*x, *y),
+        MouseScrollDelta::PixelDelta(pos) => {
+            let pixels_per_line = pixels_per_line.max(f32::EPSILON);
+
+            (pos.x as f32 / pixels_per_line, pos.y as f32 / pixels_per_line)
+        }
+    }
+}
+
 pub fn is_mouse_button_down(button: MouseButton) -> bool {
     GLOBAL_STATE.borrow().mouse_pressed.contains(&button)
 }
@@ -16,10 +96,178 @@ pub fn is_mouse_button_released(button: MouseButton) -> bool {
     GLOBAL_STATE.borrow().mouse_just_released.contains(&button)
 }
 
+/// All mouse buttons that are currently held down, in no particular order.
+pub fn pressed_mouse_buttons() -> impl Iterator<Item = MouseButton> {
+    GLOBAL_STATE
+        .borrow()
+        .mouse_pressed
+        .iter()
+        .copied()
+        .collect::<Vec<_>>()
+        .into_iter()
+}
+
+/// Number of consecutive clicks of the button, e.g. `2` right after a double
+/// click. Drops back to `0` once `double_click_time` passes without another
+/// click.
+pub fn mouse_click_count(button: MouseButton) -> u32 {
+    let max_interval = game_config().double_click_time;
+
+    GLOBAL_STATE
+        .borrow()
+        .mouse_clicks
+        .get(&button)
+        .filter(|click| click.time.elapsed().as_secs_f32() <= max_interval)
+        .map_or(0, |click| click.count)
+}
+
+/// Returns `true` on the frame the button was pressed for the second time in
+/// a row within `double_click_time`.
+pub fn is_mouse_double_clicked(button: MouseButton) -> bool {
+    is_mouse_button_pressed(button) && mouse_click_count(button) == 2
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct MouseClick {
+    pub time: Instant,
+    pub position: Vec2,
+    pub count: u32,
+}
+
+impl MouseClick {
+    /// Returns the click following `prev`, continuing its click sequence if
+    /// it's close enough in both time and position.
+    pub fn next(
+        prev: Option<MouseClick>,
+        time: Instant,
+        position: Vec2,
+        max_interval: f32,
+        max_distance: f32,
+    ) -> MouseClick {
+        let count = match prev {
+            Some(prev)
+                if time.duration_since(prev.time).as_secs_f32() <=
+                    max_interval &&
+                    prev.position.distance(position) <= max_distance =>
+            {
+                prev.count + 1
+            }
+            _ => 1,
+        };
+
+        MouseClick { time, position, count }
+    }
+}
+
+/// Returns `true` while egui wants the keyboard or the pointer, e.g. when a
+/// text field has focus or the mouse is over a window. Games can use this to
+/// skip their own input handling, see also
+/// `GameConfig::block_input_under_ui`.
+pub fn input_captured_by_ui() -> bool {
+    egui().wants_keyboard_input() || egui().wants_pointer_input()
+}
+
+/// Applies a key press to the input state, like a `KeyboardInput` event
+/// would.
+pub fn press_key(state: &mut GlobalState, keycode: KeyCode) {
+    state.pressed.insert(keycode);
+    state.just_released.remove(&keycode);
+
+    if !(game_config().block_input_under_ui && egui().wants_keyboard_input()) {
+        state.just_pressed.insert(keycode);
+        state.frame_pressed.insert(keycode);
+    }
+
+    // Key repeat sends more presses, keep the time of the first one.
+    state.key_press_times.entry(keycode).or_insert_with(real_time);
+}
+
+pub fn release_key(state: &mut GlobalState, keycode: KeyCode) {
+    state.pressed.remove(&keycode);
+    state.just_pressed.remove(&keycode);
+    state.frame_pressed.remove(&keycode);
+    state.just_released.insert(keycode);
+    state.key_press_times.remove(&keycode);
+}
+
+/// Records a mouse button press at the current mouse position. Used by the
+/// engine for both mouse and emulated touch input.
+pub fn press_mouse_button(state: &mut GlobalState, button: MouseButton) {
+    let config = game_config();
+
+    state.mouse_pressed.insert(button);
+
+    if !(config.block_input_under_ui && egui().wants_pointer_input()) {
+        state.mouse_just_pressed.insert(button);
+    }
+
+    let click = MouseClick::next(
+        state.mouse_clicks.get(&button).copied(),
+        Instant::now(),
+        state.mouse_position,
+        config.double_click_time,
+        config.double_click_distance,
+    );
+
+    state.mouse_clicks.insert(button, click);
+}
+
+pub fn release_mouse_button(state: &mut GlobalState, button: MouseButton) {
+    state.mouse_pressed.remove(&button);
+    state.mouse_just_pressed.remove(&button);
+    state.mouse_just_released.insert(button);
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TouchPhase {
+    Started,
+    Moved,
+    Ended,
+    Cancelled,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct TouchPoint {
+    pub id: u64,
+    /// Phase of the latest event of this touch. Ended and cancelled touches
+    /// are kept until the end of the frame.
+    pub phase: TouchPhase,
+    /// Position in physical pixels, like `mouse_screen()`.
+    pub position: Vec2,
+}
+
+/// All touches currently on the screen, plus the ones that ended this frame,
+/// ordered by id.
+pub fn active_touches() -> Vec<TouchPoint> {
+    GLOBAL_STATE
+        .borrow()
+        .touches
+        .values()
+        .copied()
+        .sorted_by_key(|touch| touch.id)
+        .collect()
+}
+
 pub fn is_key_pressed(keycode: KeyCode) -> bool {
     GLOBAL_STATE.borrow().just_pressed.contains(&keycode)
 }
 
+/// Whether `keycode` was pressed during the current frame. Same as
+/// `is_key_pressed` with `JustPressedMode::PerFrame`, but never sees a press
+/// twice when presses are latched until a tick. Meant for toggles that run
+/// once per frame, such as the engine's dev hotkeys.
+pub fn is_key_pressed_this_frame(keycode: KeyCode) -> bool {
+    GLOBAL_STATE.borrow().frame_pressed.contains(&keycode)
+}
+
+/// Like `is_key_pressed`, but also removes the press so later calls (and
+/// later ticks, see `GameConfig::just_pressed_mode`) no longer see it.
+/// Doesn't affect `EngineContext::input`, which was captured at the start
+/// of the frame.
+pub fn consume_just_pressed(keycode: KeyCode) -> bool {
+    GLOBAL_STATE.borrow_mut().just_pressed.remove(&keycode)
+}
+
 pub fn is_key_released(keycode: KeyCode) -> bool {
     GLOBAL_STATE.borrow().just_released.contains(&keycode)
 }
@@ -28,16 +276,94 @@ pub fn is_key_down(keycode: KeyCode) -> bool {
     GLOBAL_STATE.borrow().pressed.contains(&keycode)
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+/// Seconds since the key was pressed, or `0.0` if it isn't held.
+///
+/// Measured with `real_time()`, so it isn't affected by `time_scale()` or by
+/// the engine being paused.
+pub fn key_held_time(keycode: KeyCode) -> f32 {
+    GLOBAL_STATE
+        .borrow()
+        .key_press_times
+        .get(&keycode)
+        .map_or(0.0, |time| (real_time() - time).max(0.0) as f32)
+}
+
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Eq,
+    PartialEq,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+)]
 pub enum MouseButton {
     Left,
     Right,
     Middle,
+    /// The "back" side button found on most gaming mice.
+    Back,
+    /// The "forward" side button found on most gaming mice.
+    Forward,
+    /// Any other button, numbered the way the platform reports it.
     Other(u16),
 }
 
+impl MouseButton {
+    /// Converts a winit button, turning the platform specific codes of the
+    /// side buttons into `Back` and `Forward`.
+    pub fn from_winit(button: winit::event::MouseButton) -> Self {
+        match button {
+            winit::event::MouseButton::Left => Self::Left,
+            winit::event::MouseButton::Right => Self::Right,
+            winit::event::MouseButton::Middle => Self::Middle,
+            winit::event::MouseButton::Other(code) => {
+                match Self::side_button(code) {
+                    Some(button) => button,
+                    None => Self::Other(code),
+                }
+            }
+        }
+    }
+
+    fn side_button(code: u16) -> Option<Self> {
+        // `XBUTTON1` and `XBUTTON2`.
+        #[cfg(target_os = "windows")]
+        let (back, forward) = (&[1][..], &[2][..]);
+        // X11 buttons 8 and 9, and the evdev `BTN_SIDE` and `BTN_EXTRA`
+        // codes on Wayland.
+        #[cfg(all(unix, not(target_os = "macos")))]
+        let (back, forward) = (&[8, 0x113][..], &[9, 0x114][..]);
+        #[cfg(target_os = "macos")]
+        let (back, forward) = (&[3][..], &[4][..]);
+        // `MouseEvent.button` 3 and 4, which winit shifts down by 3.
+        #[cfg(target_arch = "wasm32")]
+        let (back, forward) = (&[0][..], &[1][..]);
+        #[cfg(not(any(windows, unix, target_arch = "wasm32")))]
+        let (back, forward): (&[u16], &[u16]) = (&[], &[]);
+
+        if back.contains(&code) {
+            Some(Self::Back)
+        } else if forward.contains(&code) {
+            Some(Self::Forward)
+        } else {
+            None
+        }
+    }
+}
+
 #[allow(dead_code)]
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+)]
 pub enum KeyCode {
     Backspace,
     Tab,
@@ -387,3 +713,71 @@ impl KeyCode {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pixel_scroll_delta_is_converted_to_lines() {
+        let delta = MouseScrollDelta::PixelDelta(
+            winit::dpi::PhysicalPosition::new(0.0, 40.0),
+        );
+
+        let (x, y) = scroll_delta_to_lines(&delta, 20.0);
+
+        assert_eq!(x, 0.0);
+        assert_eq!(y, 2.0);
+    }
+
+    #[test]
+    fn side_mouse_buttons_get_names() {
+        use winit::event::MouseButton as WinitButton;
+
+        assert_eq!(
+            MouseButton::from_winit(WinitButton::Left),
+            MouseButton::Left
+        );
+        assert_eq!(
+            MouseButton::from_winit(WinitButton::Other(200)),
+            MouseButton::Other(200)
+        );
+
+        #[cfg(all(unix, not(target_os = "macos")))]
+        {
+            assert_eq!(
+                MouseButton::from_winit(WinitButton::Other(8)),
+                MouseButton::Back
+            );
+            assert_eq!(
+                MouseButton::from_winit(WinitButton::Other(0x114)),
+                MouseButton::Forward
+            );
+        }
+    }
+
+    #[test]
+    fn logical_mouse_position_uses_scale_factor() {
+        assert_eq!(to_logical(vec2(300.0, 100.0), 2.0), vec2(150.0, 50.0));
+        assert_eq!(to_logical(vec2(300.0, 100.0), 0.0), vec2(300.0, 100.0));
+    }
+
+    #[test]
+    fn click_count_resets_outside_threshold() {
+        let start = Instant::now();
+        let at = |secs: f32| start + Duration::from_secs_f32(secs);
+
+        let first = MouseClick::next(None, at(0.0), Vec2::ZERO, 0.3, 5.0);
+        let second =
+            MouseClick::next(Some(first), at(0.2), vec2(2.0, 0.0), 0.3, 5.0);
+        let late =
+            MouseClick::next(Some(second), at(0.6), vec2(2.0, 0.0), 0.3, 5.0);
+        let far =
+            MouseClick::next(Some(late), at(0.7), vec2(50.0, 0.0), 0.3, 5.0);
+
+        assert_eq!(first.count, 1);
+        assert_eq!(second.count, 2);
+        assert_eq!(late.count, 1);
+        assert_eq!(far.count, 1);
+    }
+}
@@ -0,0 +1,59 @@
+use crate::*;
+
+static INPUT_EVENT_LOG: Lazy<Mutex<VecDeque<LoggedInputEvent>>> =
+    Lazy::new(|| Mutex::new(VecDeque::new()));
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum InputEventKind {
+    KeyPressed(KeyCode),
+    KeyReleased(KeyCode),
+    MousePressed(MouseButton),
+    MouseReleased(MouseButton),
+    /// Cursor position in physical pixels.
+    MouseMoved(Vec2),
+    /// Scroll in lines, see `GameConfig::scroll_pixels_per_line`.
+    MouseWheel(f32, f32),
+    /// New window size in physical pixels.
+    Resized(UVec2),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LoggedInputEvent {
+    /// `frame_count()` when the event arrived. Events are applied to the
+    /// input state of the following frame.
+    pub frame: u64,
+    pub kind: InputEventKind,
+}
+
+/// Called by the engine for every input event before it's applied to the
+/// input state. Keeps the last `GameConfig::input_event_history` events and
+/// `trace!`s them if `GameConfig::log_input_events` is set.
+pub fn log_input_event(kind: InputEventKind) {
+    let (enabled, history) = {
+        let config = game_config();
+        (config.log_input_events, config.input_event_history)
+    };
+
+    let event = LoggedInputEvent { frame: frame_count(), kind };
+
+    if enabled {
+        trace!("[frame {}] {:?}", event.frame, event.kind);
+    }
+
+    let mut log = INPUT_EVENT_LOG.lock();
+
+    while log.len() >= history.max(1) {
+        log.pop_front();
+    }
+
+    if history > 0 {
+        log.push_back(event);
+    }
+}
+
+/// The most recent input events, oldest first. Meant for debug overlays
+/// when tracking down input bugs, unlike `start_input_recording` it's always
+/// on and only keeps a short history.
+pub fn recent_input_events() -> Vec<LoggedInputEvent> {
+    INPUT_EVENT_LOG.lock().iter().copied().collect()
+}
@@ -0,0 +1,734 @@
+use std::collections::BTreeMap;
+
+use serde::{
+    de::{EnumAccess, IgnoredAny, MapAccess, VariantAccess, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
+
+use crate::*;
+
+/// A single input that can trigger an action.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum InputBinding {
+    Key(KeyCode),
+    /// A physical key regardless of the keyboard layout, see `ScanCode`.
+    ScanCode(ScanCode),
+    Mouse(MouseButton),
+    /// The button on any connected gamepad.
+    GamepadButton(GamepadButton),
+    /// Counts as held while the axis of any connected gamepad is pushed past
+    /// `threshold`, towards negative values if `threshold` is negative.
+    ///
+    /// Axes don't have just pressed/released states, so these bindings are
+    /// only considered by `InputMap::is_pressed` and `InputMap::axis`.
+    GamepadAxis {
+        axis: GamepadAxis,
+        threshold: f32,
+    },
+}
+
+impl InputBinding {
+    /// Binds the key at the position `key` has on a US QWERTY keyboard, e.g.
+    /// `InputBinding::physical(KeyCode::W)` for forward that stays in the
+    /// WASD spot on AZERTY. Falls back to `InputBinding::Key` where
+    /// `ScanCode::from_qwerty` doesn't know the key.
+    pub fn physical(key: KeyCode) -> Self {
+        match ScanCode::from_qwerty(key) {
+            Some(scancode) => Self::ScanCode(scancode),
+            None => Self::Key(key),
+        }
+    }
+
+    fn is_down(&self, state: &GlobalState) -> bool {
+        match *self {
+            Self::Key(key) => state.pressed.contains(&key),
+            Self::ScanCode(code) => state.pressed_scancodes.contains(&code),
+            Self::Mouse(button) => state.mouse_pressed.contains(&button),
+            Self::GamepadButton(button) => {
+                state.gamepads.values().any(|x| x.pressed.contains(&button))
+            }
+            Self::GamepadAxis { .. } => self.value(state) > 0.0,
+        }
+    }
+
+    fn is_just_pressed(&self, state: &GlobalState) -> bool {
+        match *self {
+            Self::Key(key) => state.just_pressed.contains(&key),
+            Self::ScanCode(code) => {
+                state.just_pressed_scancodes.contains(&code)
+            }
+            Self::Mouse(button) => state.mouse_just_pressed.contains(&button),
+            Self::GamepadButton(button) => {
+                state
+                    .gamepads
+                    .values()
+                    .any(|x| x.just_pressed.contains(&button))
+            }
+            Self::GamepadAxis { .. } => false,
+        }
+    }
+
+    fn is_just_released(&self, state: &GlobalState) -> bool {
+        match *self {
+            Self::Key(key) => state.just_released.contains(&key),
+            Self::ScanCode(code) => {
+                state.just_released_scancodes.contains(&code)
+            }
+            Self::Mouse(button) => state.mouse_just_released.contains(&button),
+            Self::GamepadButton(button) => {
+                state
+                    .gamepads
+                    .values()
+                    .any(|x| x.just_released.contains(&button))
+            }
+            Self::GamepadAxis { .. } => false,
+        }
+    }
+
+    /// How strongly the binding is held in `0.0..=1.0`, buttons are either
+    /// `0.0` or `1.0`.
+    fn value(&self, state: &GlobalState) -> f32 {
+        match *self {
+            Self::GamepadAxis { axis, threshold } => {
+                let value = state
+                    .gamepads
+                    .values()
+                    .filter_map(|x| x.axes.get(&axis).copied())
+                    .map(|x| if threshold < 0.0 { -x } else { x })
+                    .fold(0.0, f32::max);
+
+                if value > threshold.abs() {
+                    value.min(1.0)
+                } else {
+                    0.0
+                }
+            }
+            _ => {
+                if self.is_down(state) {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
+
+/// An axis made of two sets of bindings pulling in opposite directions,
+/// e.g. A/D or the left and right halves of a stick.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct InputAxis {
+    pub negative: Vec<InputBinding>,
+    pub positive: Vec<InputBinding>,
+}
+
+impl InputAxis {
+    pub fn new(negative: InputBinding, positive: InputBinding) -> Self {
+        Self { negative: vec![negative], positive: vec![positive] }
+    }
+
+    /// Both directions of a gamepad axis, ignoring values below `deadzone`.
+    pub fn gamepad(axis: GamepadAxis, deadzone: f32) -> Self {
+        let deadzone = deadzone.abs();
+
+        Self::new(
+            InputBinding::GamepadAxis { axis, threshold: -deadzone },
+            InputBinding::GamepadAxis { axis, threshold: deadzone },
+        )
+    }
+
+    fn value(&self, state: &GlobalState) -> f32 {
+        let strength = |bindings: &[InputBinding]| {
+            bindings.iter().map(|x| x.value(state)).fold(0.0, f32::max)
+        };
+
+        (strength(&self.positive) - strength(&self.negative)).clamp(-1.0, 1.0)
+    }
+}
+
+/// Named actions and axes mapped to their bindings, so that games can ask
+/// for `c.is_action_pressed("jump")` instead of checking keys directly. The
+/// state is read from the regular input state every time it's queried.
+///
+/// The active map lives on `EngineState`, initialized from
+/// `game_config().input_map` and changed at runtime through
+/// `EngineContext::input_map`, e.g. from a rebinding menu.
+///
+/// ```ignore
+/// let mut map = InputMap::new();
+/// map.bind("jump", InputBinding::Key(KeyCode::Space));
+/// map.bind("jump", InputBinding::GamepadButton(GamepadButton::South));
+/// map.bind_axis("move_x", InputAxis::new(
+///     InputBinding::Key(KeyCode::A),
+///     InputBinding::Key(KeyCode::D),
+/// ));
+/// ```
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct InputMap {
+    pub actions: HashMap<String, Vec<InputBinding>>,
+    pub axes: HashMap<String, Vec<InputAxis>>,
+}
+
+/// File format used by `InputMap::save_with_format` and
+/// `InputMap::load_with_format`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum InputMapFormat {
+    #[default]
+    Ron,
+    Json,
+}
+
+impl InputMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Writes the bindings to `path` as RON, e.g. from a settings menu after
+    /// the player changed them.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        self.save_with_format(path, InputMapFormat::Ron)
+    }
+
+    /// Reads bindings written by `save`.
+    ///
+    /// Bindings this version doesn't know about, e.g. a renamed key or a
+    /// file written by a newer build, are skipped with a warning instead of
+    /// failing the whole load.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::load_with_format(path, InputMapFormat::Ron)
+    }
+
+    pub fn save_with_format(
+        &self,
+        path: impl AsRef<Path>,
+        format: InputMapFormat,
+    ) -> Result<()> {
+        // Sorted so that saving the same bindings twice gives the same file.
+        let file = SavedInputMap {
+            actions: self
+                .actions
+                .iter()
+                .map(|(name, bindings)| (name.as_str(), bindings.as_slice()))
+                .collect(),
+            axes: self
+                .axes
+                .iter()
+                .map(|(name, axes)| (name.as_str(), axes.as_slice()))
+                .collect(),
+        };
+
+        let contents = match format {
+            InputMapFormat::Ron => {
+                ron::ser::to_string_pretty(&file, Default::default())?
+            }
+            InputMapFormat::Json => serde_json::to_string_pretty(&file)?,
+        };
+
+        std::fs::write(path.as_ref(), contents)?;
+
+        Ok(())
+    }
+
+    pub fn load_with_format(
+        path: impl AsRef<Path>,
+        format: InputMapFormat,
+    ) -> Result<Self> {
+        let contents = std::fs::read_to_string(path.as_ref())?;
+
+        let file: LoadedInputMap = match format {
+            InputMapFormat::Ron => ron::from_str(&contents)?,
+            InputMapFormat::Json => serde_json::from_str(&contents)?,
+        };
+
+        let known = |bindings: Vec<LoadedBinding>| {
+            bindings.into_iter().filter_map(|x| x.0).collect::<Vec<_>>()
+        };
+
+        Ok(Self {
+            actions: file
+                .actions
+                .into_iter()
+                .map(|(name, bindings)| (name, known(bindings)))
+                .collect(),
+            axes: file
+                .axes
+                .into_iter()
+                .map(|(name, axes)| {
+                    let axes = axes
+                        .into_iter()
+                        .map(|axis| {
+                            InputAxis {
+                                negative: known(axis.negative),
+                                positive: known(axis.positive),
+                            }
+                        })
+                        .collect();
+
+                    (name, axes)
+                })
+                .collect(),
+        })
+    }
+
+    /// Adds a binding to the action, keeping the existing ones.
+    pub fn bind(&mut self, action: &str, binding: InputBinding) -> &mut Self {
+        let bindings = self.actions.entry(action.to_string()).or_default();
+
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+
+        self
+    }
+
+    /// Replaces all bindings of the action.
+    pub fn set_bindings(&mut self, action: &str, bindings: Vec<InputBinding>) {
+        self.actions.insert(action.to_string(), bindings);
+    }
+
+    pub fn unbind_all(&mut self, action: &str) {
+        self.actions.remove(action);
+    }
+
+    pub fn bindings(&self, action: &str) -> &[InputBinding] {
+        self.actions.get(action).map_or(&[], |x| x.as_slice())
+    }
+
+    /// Adds an axis, combined with the existing ones of the same name by
+    /// taking the strongest input.
+    pub fn bind_axis(&mut self, name: &str, axis: InputAxis) -> &mut Self {
+        self.axes.entry(name.to_string()).or_default().push(axis);
+        self
+    }
+
+    /// Returns `true` while any binding of the action is held, like
+    /// `is_key_down`.
+    pub fn is_pressed(&self, action: &str) -> bool {
+        self.is_pressed_in(&GLOBAL_STATE.borrow(), action)
+    }
+
+    /// Returns `true` on the frame any binding of the action was pressed,
+    /// like `is_key_pressed`.
+    pub fn is_just_pressed(&self, action: &str) -> bool {
+        self.is_just_pressed_in(&GLOBAL_STATE.borrow(), action)
+    }
+
+    /// Returns `true` on the frame any binding of the action was released.
+    pub fn is_just_released(&self, action: &str) -> bool {
+        self.is_just_released_in(&GLOBAL_STATE.borrow(), action)
+    }
+
+    /// Value of the axis in `-1.0..=1.0`, `0.0` if there's no such axis.
+    pub fn axis(&self, name: &str) -> f32 {
+        self.axis_in(&GLOBAL_STATE.borrow(), name)
+    }
+
+    fn is_pressed_in(&self, state: &GlobalState, action: &str) -> bool {
+        self.bindings(action).iter().any(|x| x.is_down(state))
+    }
+
+    fn is_just_pressed_in(&self, state: &GlobalState, action: &str) -> bool {
+        self.bindings(action).iter().any(|x| x.is_just_pressed(state))
+    }
+
+    fn is_just_released_in(&self, state: &GlobalState, action: &str) -> bool {
+        self.bindings(action).iter().any(|x| x.is_just_released(state))
+    }
+
+    fn axis_in(&self, state: &GlobalState, name: &str) -> f32 {
+        let Some(axes) = self.axes.get(name) else {
+            return 0.0;
+        };
+
+        axes.iter().map(|x| x.value(state)).fold(0.0, |acc: f32, x| {
+            if x.abs() > acc.abs() {
+                x
+            } else {
+                acc
+            }
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct SavedInputMap<'a> {
+    actions: BTreeMap<&'a str, &'a [InputBinding]>,
+    axes: BTreeMap<&'a str, &'a [InputAxis]>,
+}
+
+#[derive(Deserialize)]
+struct LoadedInputMap {
+    #[serde(default)]
+    actions: HashMap<String, Vec<LoadedBinding>>,
+    #[serde(default)]
+    axes: HashMap<String, Vec<LoadedAxis>>,
+}
+
+#[derive(Deserialize)]
+struct LoadedAxis {
+    #[serde(default)]
+    negative: Vec<LoadedBinding>,
+    #[serde(default)]
+    positive: Vec<LoadedBinding>,
+}
+
+/// An `InputBinding` that's `None` if it uses a name we don't know.
+///
+/// The derived `Deserialize` would fail the whole file, and going through
+/// `#[serde(untagged)]` doesn't work for RON since it loses the enum variant
+/// names, so the variants are matched by hand here.
+struct LoadedBinding(Option<InputBinding>);
+
+impl<'de> Deserialize<'de> for LoadedBinding {
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        deserializer.deserialize_enum(
+            "InputBinding",
+            &["Key", "ScanCode", "Mouse", "GamepadButton", "GamepadAxis"],
+            LoadedBindingVisitor,
+        )
+    }
+}
+
+struct LoadedBindingVisitor;
+
+impl<'de> Visitor<'de> for LoadedBindingVisitor {
+    type Value = LoadedBinding;
+
+    fn expecting(
+        &self,
+        formatter: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        formatter.write_str("an input binding")
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(
+        self,
+        data: A,
+    ) -> std::result::Result<Self::Value, A::Error> {
+        let (Name(variant), content) = data.variant::<Name>()?;
+
+        let binding = match variant.as_str() {
+            "Key" => {
+                content
+                    .newtype_variant::<Known<KeyCode>>()?
+                    .0
+                    .map(InputBinding::Key)
+            }
+            "ScanCode" => {
+                Some(InputBinding::ScanCode(
+                    content.newtype_variant::<ScanCode>()?,
+                ))
+            }
+            "Mouse" => {
+                content
+                    .newtype_variant::<KnownMouseButton>()?
+                    .0
+                    .map(InputBinding::Mouse)
+            }
+            "GamepadButton" => {
+                content
+                    .newtype_variant::<Known<GamepadButton>>()?
+                    .0
+                    .map(InputBinding::GamepadButton)
+            }
+            "GamepadAxis" => {
+                content.struct_variant(
+                    &["axis", "threshold"],
+                    GamepadAxisVisitor,
+                )?
+            }
+            _ => {
+                warn!("Skipping unknown input binding {}", variant);
+                content.newtype_variant::<IgnoredAny>()?;
+                None
+            }
+        };
+
+        Ok(LoadedBinding(binding))
+    }
+}
+
+struct GamepadAxisVisitor;
+
+impl<'de> Visitor<'de> for GamepadAxisVisitor {
+    type Value = Option<InputBinding>;
+
+    fn expecting(
+        &self,
+        formatter: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        formatter.write_str("a gamepad axis binding")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(
+        self,
+        mut map: A,
+    ) -> std::result::Result<Self::Value, A::Error> {
+        let mut axis = None;
+        let mut threshold = None;
+
+        while let Some(Name(key)) = map.next_key::<Name>()? {
+            match key.as_str() {
+                "axis" => {
+                    axis = Some(map.next_value::<Known<GamepadAxis>>()?.0)
+                }
+                "threshold" => threshold = Some(map.next_value::<f32>()?),
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        let axis =
+            axis.ok_or_else(|| serde::de::Error::missing_field("axis"))?;
+        let threshold = threshold
+            .ok_or_else(|| serde::de::Error::missing_field("threshold"))?;
+
+        Ok(axis.map(|axis| InputBinding::GamepadAxis { axis, threshold }))
+    }
+}
+
+/// A bare identifier like a variant or field name. RON can't read these as
+/// regular strings.
+struct Name(String);
+
+impl<'de> Deserialize<'de> for Name {
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        struct NameVisitor;
+
+        impl<'de> Visitor<'de> for NameVisitor {
+            type Value = Name;
+
+            fn expecting(
+                &self,
+                formatter: &mut std::fmt::Formatter,
+            ) -> std::fmt::Result {
+                formatter.write_str("a name")
+            }
+
+            fn visit_str<E: serde::de::Error>(
+                self,
+                value: &str,
+            ) -> std::result::Result<Self::Value, E> {
+                Ok(Name(value.to_string()))
+            }
+        }
+
+        deserializer.deserialize_identifier(NameVisitor)
+    }
+}
+
+/// A unit-only enum like `KeyCode` that's `None` for unknown variants.
+struct Known<T>(Option<T>);
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Known<T> {
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        struct KnownVisitor<T>(std::marker::PhantomData<T>);
+
+        impl<'de, T: Deserialize<'de>> Visitor<'de> for KnownVisitor<T> {
+            type Value = Known<T>;
+
+            fn expecting(
+                &self,
+                formatter: &mut std::fmt::Formatter,
+            ) -> std::fmt::Result {
+                formatter.write_str("a variant name")
+            }
+
+            fn visit_enum<A: EnumAccess<'de>>(
+                self,
+                data: A,
+            ) -> std::result::Result<Self::Value, A::Error> {
+                let (Name(name), content) = data.variant::<Name>()?;
+                content.unit_variant()?;
+
+                Ok(Known(known_unit_variant(&name)))
+            }
+        }
+
+        deserializer.deserialize_enum(
+            std::any::type_name::<T>(),
+            &[],
+            KnownVisitor(std::marker::PhantomData),
+        )
+    }
+}
+
+/// Parses `name` as a unit variant of `T`, logging and skipping it if `T`
+/// has no such variant.
+fn known_unit_variant<'de, T: Deserialize<'de>>(name: &str) -> Option<T> {
+    use serde::de::IntoDeserializer;
+
+    T::deserialize(name.into_deserializer())
+        .map_err(|_: serde::de::value::Error| {
+            warn!("Skipping unknown input binding {}", name);
+        })
+        .ok()
+}
+
+/// Like `Known`, but also accepts `MouseButton::Other(n)`.
+struct KnownMouseButton(Option<MouseButton>);
+
+impl<'de> Deserialize<'de> for KnownMouseButton {
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        struct KnownMouseButtonVisitor;
+
+        impl<'de> Visitor<'de> for KnownMouseButtonVisitor {
+            type Value = KnownMouseButton;
+
+            fn expecting(
+                &self,
+                formatter: &mut std::fmt::Formatter,
+            ) -> std::fmt::Result {
+                formatter.write_str("a mouse button")
+            }
+
+            fn visit_enum<A: EnumAccess<'de>>(
+                self,
+                data: A,
+            ) -> std::result::Result<Self::Value, A::Error> {
+                let (Name(name), content) = data.variant::<Name>()?;
+
+                if name == "Other" {
+                    let button = content.newtype_variant::<u16>()?;
+                    return Ok(KnownMouseButton(Some(MouseButton::Other(
+                        button,
+                    ))));
+                }
+
+                content.unit_variant()?;
+
+                Ok(KnownMouseButton(known_unit_variant(&name)))
+            }
+        }
+
+        deserializer.deserialize_enum(
+            "MouseButton",
+            &[],
+            KnownMouseButtonVisitor,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn actions_read_the_input_state() {
+        let mut map = InputMap::new();
+        map.bind("jump", InputBinding::Key(KeyCode::Space));
+        map.bind_axis(
+            "move_x",
+            InputAxis::new(
+                InputBinding::Key(KeyCode::Left),
+                InputBinding::Key(KeyCode::Right),
+            ),
+        );
+
+        let mut state = GlobalState::default();
+        state.pressed.insert(KeyCode::Space);
+        state.pressed.insert(KeyCode::Left);
+        state.just_pressed.insert(KeyCode::Space);
+
+        assert!(map.is_pressed_in(&state, "jump"));
+        assert!(map.is_just_pressed_in(&state, "jump"));
+        assert!(!map.is_just_released_in(&state, "jump"));
+        assert!(!map.is_pressed_in(&state, "crouch"));
+        assert_eq!(map.axis_in(&state, "move_x"), -1.0);
+
+        state.just_pressed.clear();
+        assert!(map.is_pressed_in(&state, "jump"));
+        assert!(!map.is_just_pressed_in(&state, "jump"));
+    }
+
+    #[test]
+    fn loading_skips_unknown_bindings() {
+        let ron = r#"(
+            actions: {
+                "jump": [Key(Space), Key(NotAKey), Touch(3)],
+                "fire": [
+                    Mouse(Left), Mouse(Other(7)), Mouse(NotAButton),
+                    GamepadButton(South), ScanCode(17),
+                ],
+            },
+            axes: {
+                "move_x": [(
+                    negative: [GamepadAxis(axis: LeftStickX, threshold: -0.2)],
+                    positive: [GamepadAxis(axis: NotAnAxis, threshold: 0.2)],
+                )],
+            },
+        )"#;
+
+        let json = r#"{
+            "actions": {
+                "jump": [{"Key": "Space"}, {"Key": "NotAKey"}, {"Touch": 3}],
+                "fire": [
+                    {"Mouse": "Left"}, {"Mouse": {"Other": 7}}, {"Mouse": "NotAButton"},
+                    {"GamepadButton": "South"}, {"ScanCode": 17}
+                ]
+            },
+            "axes": {
+                "move_x": [{
+                    "negative": [{"GamepadAxis": {"axis": "LeftStickX", "threshold": -0.2}}],
+                    "positive": [{"GamepadAxis": {"axis": "NotAnAxis", "threshold": 0.2}}]
+                }]
+            }
+        }"#;
+
+        // Unique per run, so parallel or repeated test runs don't share files.
+        let dir = std::env::temp_dir().join(format!(
+            "comfy-input-map-{}-{}",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        for (format, contents) in
+            [(InputMapFormat::Ron, ron), (InputMapFormat::Json, json)]
+        {
+            let path = dir.join(format!("comfy-input-map-{:?}", format));
+            std::fs::write(&path, contents).unwrap();
+
+            let map = InputMap::load_with_format(&path, format).unwrap();
+
+            assert_eq!(map.bindings("jump"), &[InputBinding::Key(
+                KeyCode::Space
+            )]);
+            assert_eq!(map.bindings("fire"), &[
+                InputBinding::Mouse(MouseButton::Left),
+                InputBinding::Mouse(MouseButton::Other(7)),
+                InputBinding::GamepadButton(GamepadButton::South),
+                InputBinding::ScanCode(ScanCode(17)),
+            ]);
+            assert_eq!(map.axes["move_x"], vec![InputAxis {
+                negative: vec![InputBinding::GamepadAxis {
+                    axis: GamepadAxis::LeftStickX,
+                    threshold: -0.2,
+                }],
+                positive: vec![],
+            }]);
+
+            // Round trips through the same format.
+            map.save_with_format(&path, format).unwrap();
+            let reloaded = InputMap::load_with_format(&path, format).unwrap();
+            assert_eq!(reloaded.actions, map.actions);
+            assert_eq!(reloaded.axes, map.axes);
+
+            std::fs::remove_file(&path).ok();
+        }
+
+        std::fs::remove_dir(&dir).ok();
+    }
+}
@@ -0,0 +1,271 @@
+use std::io::{BufRead, BufWriter, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+static INPUT_RECORDING: Lazy<AtomicRefCell<InputRecording>> =
+    Lazy::new(|| AtomicRefCell::new(InputRecording::Idle));
+
+enum InputRecording {
+    Idle,
+    Recording { writer: BufWriter<std::fs::File>, frame: u64 },
+    Playback { frames: VecDeque<InputFrame> },
+}
+
+/// Snapshot of the input state of a single frame. Recordings are stored as
+/// one JSON encoded `InputFrame` per line.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct InputFrame {
+    /// Frame number relative to the start of the recording.
+    pub frame: u64,
+
+    pub pressed: Vec<KeyCode>,
+    pub just_pressed: Vec<KeyCode>,
+    pub just_released: Vec<KeyCode>,
+
+    #[serde(default)]
+    pub pressed_scancodes: Vec<ScanCode>,
+    #[serde(default)]
+    pub just_pressed_scancodes: Vec<ScanCode>,
+    #[serde(default)]
+    pub just_released_scancodes: Vec<ScanCode>,
+
+    pub mouse_pressed: Vec<MouseButton>,
+    pub mouse_just_pressed: Vec<MouseButton>,
+    pub mouse_just_released: Vec<MouseButton>,
+
+    pub mouse_position: [f32; 2],
+    pub mouse_wheel: (f32, f32),
+    pub mouse_delta: [f32; 2],
+
+    pub text_input: String,
+
+    /// How long each held key has been held in seconds, for
+    /// `key_held_time`.
+    #[serde(default)]
+    pub key_held_times: Vec<(KeyCode, f64)>,
+    /// The last click of each button, for `mouse_click_count`.
+    #[serde(default)]
+    pub mouse_clicks: Vec<RecordedClick>,
+}
+
+/// A `MouseClick` with its time stored as the number of seconds before the
+/// frame it was recorded in.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct RecordedClick {
+    pub button: MouseButton,
+    pub age: f32,
+    pub position: [f32; 2],
+    pub count: u32,
+}
+
+impl InputFrame {
+    pub fn capture(state: &GlobalState, frame: u64) -> Self {
+        Self {
+            frame,
+
+            pressed: state.pressed.iter().copied().collect(),
+            just_pressed: state.just_pressed.iter().copied().collect(),
+            just_released: state.just_released.iter().copied().collect(),
+
+            pressed_scancodes: state
+                .pressed_scancodes
+                .iter()
+                .copied()
+                .collect(),
+            just_pressed_scancodes: state
+                .just_pressed_scancodes
+                .iter()
+                .copied()
+                .collect(),
+            just_released_scancodes: state
+                .just_released_scancodes
+                .iter()
+                .copied()
+                .collect(),
+
+            mouse_pressed: state.mouse_pressed.iter().copied().collect(),
+            mouse_just_pressed: state
+                .mouse_just_pressed
+                .iter()
+                .copied()
+                .collect(),
+            mouse_just_released: state
+                .mouse_just_released
+                .iter()
+                .copied()
+                .collect(),
+
+            mouse_position: state.mouse_position.to_array(),
+            mouse_wheel: state.mouse_wheel,
+            mouse_delta: state.mouse_delta.to_array(),
+
+            text_input: state.text_input.clone(),
+
+            key_held_times: state
+                .key_press_times
+                .iter()
+                .map(|(key, time)| (*key, (real_time() - time).max(0.0)))
+                .collect(),
+            mouse_clicks: state
+                .mouse_clicks
+                .iter()
+                .map(|(button, click)| {
+                    RecordedClick {
+                        button: *button,
+                        age: click.time.elapsed().as_secs_f32(),
+                        position: click.position.to_array(),
+                        count: click.count,
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    /// Overwrites the input state with this frame.
+    pub fn apply(self, state: &mut GlobalState) {
+        state.pressed = self.pressed.into_iter().collect();
+        state.frame_pressed = self.just_pressed.iter().copied().collect();
+        state.just_pressed = self.just_pressed.into_iter().collect();
+        state.just_released = self.just_released.into_iter().collect();
+
+        state.pressed_scancodes = self.pressed_scancodes.into_iter().collect();
+        state.just_pressed_scancodes =
+            self.just_pressed_scancodes.into_iter().collect();
+        state.just_released_scancodes =
+            self.just_released_scancodes.into_iter().collect();
+
+        state.mouse_pressed = self.mouse_pressed.into_iter().collect();
+        state.mouse_just_pressed =
+            self.mouse_just_pressed.into_iter().collect();
+        state.mouse_just_released =
+            self.mouse_just_released.into_iter().collect();
+
+        let mouse_position = Vec2::from_array(self.mouse_position);
+        state.mouse_moved = state.mouse_position != mouse_position;
+        state.mouse_position = mouse_position;
+        state.mouse_wheel = self.mouse_wheel;
+        state.mouse_delta = Vec2::from_array(self.mouse_delta);
+
+        state.text_input = self.text_input;
+
+        let now = real_time();
+        state.key_press_times = self
+            .key_held_times
+            .into_iter()
+            .map(|(key, held)| (key, now - held))
+            .collect();
+
+        let now = Instant::now();
+        state.mouse_clicks = self
+            .mouse_clicks
+            .into_iter()
+            .map(|click| {
+                let time = now
+                    .checked_sub(Duration::from_secs_f32(click.age.max(0.0)))
+                    .unwrap_or(now);
+
+                (click.button, MouseClick {
+                    time,
+                    position: Vec2::from_array(click.position),
+                    count: click.count,
+                })
+            })
+            .collect();
+    }
+}
+
+/// Starts writing the input state of every frame to `path`, replacing any
+/// recording or playback in progress.
+///
+/// Combined with `GameConfig::fixed_frame_delta` and `rng_seed` a recording
+/// can be played back with `start_input_playback` to reproduce a run.
+pub fn start_input_recording(path: impl AsRef<Path>) -> Result<()> {
+    let file = std::fs::File::create(path.as_ref())?;
+
+    stop_input_recording();
+
+    *INPUT_RECORDING.borrow_mut() =
+        InputRecording::Recording { writer: BufWriter::new(file), frame: 0 };
+
+    Ok(())
+}
+
+/// Stops and flushes the current recording, if any. Also stops playback.
+pub fn stop_input_recording() {
+    let recording = std::mem::replace(
+        &mut *INPUT_RECORDING.borrow_mut(),
+        InputRecording::Idle,
+    );
+
+    if let InputRecording::Recording { mut writer, .. } = recording {
+        writer.flush().log_err();
+    }
+}
+
+/// Replays a recording made with `start_input_recording`. While active, live
+/// input events are ignored and the input state is overwritten from the
+/// recording each frame. Stops on its own at the end of the recording.
+pub fn start_input_playback(path: impl AsRef<Path>) -> Result<()> {
+    let file = std::io::BufReader::new(std::fs::File::open(path.as_ref())?);
+
+    let mut frames = VecDeque::new();
+
+    for line in file.lines() {
+        let line = line?;
+
+        if !line.trim().is_empty() {
+            frames.push_back(serde_json::from_str::<InputFrame>(&line)?);
+        }
+    }
+
+    stop_input_recording();
+
+    *INPUT_RECORDING.borrow_mut() = InputRecording::Playback { frames };
+
+    Ok(())
+}
+
+pub fn is_input_recording_active() -> bool {
+    matches!(*INPUT_RECORDING.borrow(), InputRecording::Recording { .. })
+}
+
+pub fn is_input_playback_active() -> bool {
+    matches!(*INPUT_RECORDING.borrow(), InputRecording::Playback { .. })
+}
+
+/// Records or replays the input of the current frame. Called by the engine
+/// after all input events of the frame have been processed and before the
+/// game update.
+pub fn update_input_recording() {
+    let mut recording = INPUT_RECORDING.borrow_mut();
+
+    match &mut *recording {
+        InputRecording::Idle => {}
+
+        InputRecording::Recording { writer, frame } => {
+            let input = InputFrame::capture(&GLOBAL_STATE.borrow(), *frame);
+            *frame += 1;
+
+            let result = serde_json::to_writer(&mut *writer, &input)
+                .map_err(anyhow::Error::from)
+                .and_then(|_| Ok(writer.write_all(b"\n")?));
+
+            if let Err(err) = result {
+                error!("Failed to write input recording: {}", err);
+                *recording = InputRecording::Idle;
+            }
+        }
+
+        InputRecording::Playback { frames } => {
+            match frames.pop_front() {
+                Some(input) => input.apply(&mut GLOBAL_STATE.borrow_mut()),
+                None => {
+                    info!("Input playback finished");
+                    *recording = InputRecording::Idle;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use crate::*;
+
+/// Copy of the frame's keyboard and mouse state, captured once per frame
+/// after all input events were processed and passed around as
+/// `EngineContext::input`.
+///
+/// Reading from the snapshot doesn't touch `GLOBAL_STATE`, so it's cheap to
+/// query from many systems. The methods match the free functions of the same
+/// name, e.g. `c.input.is_key_pressed(KeyCode::Space)`. The sets are reused
+/// between frames, so capturing doesn't allocate once they've grown.
+#[derive(Clone, Debug, Default)]
+pub struct InputSnapshot {
+    pressed: HashSet<KeyCode>,
+    just_pressed: HashSet<KeyCode>,
+    just_released: HashSet<KeyCode>,
+
+    pressed_scancodes: HashSet<ScanCode>,
+    just_pressed_scancodes: HashSet<ScanCode>,
+    just_released_scancodes: HashSet<ScanCode>,
+
+    mouse_pressed: HashSet<MouseButton>,
+    mouse_just_pressed: HashSet<MouseButton>,
+    mouse_just_released: HashSet<MouseButton>,
+
+    mouse_position: Vec2,
+    mouse_wheel: (f32, f32),
+    mouse_delta: Vec2,
+}
+
+impl InputSnapshot {
+    /// Overwrites the snapshot with the current state, keeping the capacity
+    /// of the sets.
+    pub fn capture(&mut self, state: &GlobalState) {
+        fn copy<T: Copy + Eq + std::hash::Hash>(
+            dst: &mut HashSet<T>,
+            src: &HashSet<T>,
+        ) {
+            dst.clear();
+            dst.extend(src.iter().copied());
+        }
+
+        copy(&mut self.pressed, &state.pressed);
+        copy(&mut self.just_pressed, &state.just_pressed);
+        copy(&mut self.just_released, &state.just_released);
+
+        copy(&mut self.pressed_scancodes, &state.pressed_scancodes);
+        copy(&mut self.just_pressed_scancodes, &state.just_pressed_scancodes);
+        copy(&mut self.just_released_scancodes, &state.just_released_scancodes);
+
+        copy(&mut self.mouse_pressed, &state.mouse_pressed);
+        copy(&mut self.mouse_just_pressed, &state.mouse_just_pressed);
+        copy(&mut self.mouse_just_released, &state.mouse_just_released);
+
+        self.mouse_position = state.mouse_position;
+        self.mouse_wheel = state.mouse_wheel;
+        self.mouse_delta = state.mouse_delta;
+    }
+
+    pub fn is_key_pressed(&self, keycode: KeyCode) -> bool {
+        self.just_pressed.contains(&keycode)
+    }
+
+    pub fn is_key_released(&self, keycode: KeyCode) -> bool {
+        self.just_released.contains(&keycode)
+    }
+
+    pub fn is_key_down(&self, keycode: KeyCode) -> bool {
+        self.pressed.contains(&keycode)
+    }
+
+    pub fn is_scancode_pressed(&self, scancode: ScanCode) -> bool {
+        self.just_pressed_scancodes.contains(&scancode)
+    }
+
+    pub fn is_scancode_released(&self, scancode: ScanCode) -> bool {
+        self.just_released_scancodes.contains(&scancode)
+    }
+
+    pub fn is_scancode_down(&self, scancode: ScanCode) -> bool {
+        self.pressed_scancodes.contains(&scancode)
+    }
+
+    pub fn is_mouse_button_down(&self, button: MouseButton) -> bool {
+        self.mouse_pressed.contains(&button)
+    }
+
+    pub fn is_mouse_button_pressed(&self, button: MouseButton) -> bool {
+        self.mouse_just_pressed.contains(&button)
+    }
+
+    pub fn is_mouse_button_released(&self, button: MouseButton) -> bool {
+        self.mouse_just_released.contains(&button)
+    }
+
+    /// Same as `mouse_screen()`. There's no world position on the snapshot
+    /// since it depends on the camera, which can still move during the
+    /// frame, use `mouse_world()` for that.
+    pub fn mouse_screen(&self) -> Vec2 {
+        self.mouse_position
+    }
+
+    pub fn mouse_wheel(&self) -> (f32, f32) {
+        self.mouse_wheel
+    }
+
+    pub fn mouse_delta(&self) -> Vec2 {
+        self.mouse_delta
+    }
+}
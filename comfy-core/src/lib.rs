@@ -6,49 +6,73 @@ mod assets;
 mod audio;
 mod blood_canvas;
 mod camera;
+mod clipboard;
 mod config;
 #[cfg(not(target_arch = "wasm32"))]
 mod desktop;
+mod engine_commands;
 mod errors;
 mod events;
 mod fast_sprite;
+mod frame_stats;
+mod gamepad;
 mod global_state;
 mod input;
+mod input_log;
+mod input_map;
+mod input_recording;
+mod input_snapshot;
 mod lighting;
 mod math;
 mod perf_counters;
 mod quad;
 pub mod random;
+mod scancode;
+mod screenshot;
 mod shaders;
 pub mod spatial_hash;
 mod task_timer;
 mod text;
 mod timer;
 mod tween;
+mod user_event;
+mod window;
 
 pub use crate::asset_loader::*;
 pub use crate::assets::*;
 pub use crate::audio::*;
 pub use crate::blood_canvas::*;
 pub use crate::camera::*;
+pub use crate::clipboard::*;
 pub use crate::config::*;
 #[cfg(not(target_arch = "wasm32"))]
 pub use crate::desktop::*;
+pub use crate::engine_commands::*;
 pub use crate::errors::*;
 pub use crate::events::*;
 pub use crate::fast_sprite::*;
+pub use crate::frame_stats::*;
+pub use crate::gamepad::*;
 pub use crate::global_state::*;
 pub use crate::input::*;
+pub use crate::input_log::*;
+pub use crate::input_map::*;
+pub use crate::input_recording::*;
+pub use crate::input_snapshot::*;
 pub use crate::lighting::*;
 pub use crate::math::*;
 pub use crate::perf_counters::*;
 pub use crate::quad::*;
 pub use crate::random::*;
+pub use crate::scancode::*;
+pub use crate::screenshot::*;
 pub use crate::shaders::*;
 pub use crate::task_timer::*;
 pub use crate::text::*;
 pub use crate::timer::*;
 pub use crate::tween::*;
+pub use crate::user_event::*;
+pub use crate::window::*;
 
 pub use std::any::Any;
 pub use std::collections::VecDeque;
@@ -85,13 +109,15 @@ pub use instant::{Duration, Instant};
 #[cfg(not(target_arch = "wasm32"))]
 pub use notify;
 #[cfg(not(target_arch = "wasm32"))]
+pub use rayon;
+#[cfg(not(target_arch = "wasm32"))]
 pub use std::time::{Duration, Instant};
 
 pub use inline_tweak;
 pub use inline_tweak::tweak;
 
 pub use std::future::Future;
-pub use std::path::Path;
+pub use std::path::{Path, PathBuf};
 pub use std::pin::Pin;
 pub use std::task::Poll;
 
@@ -145,8 +171,8 @@ pub use tracy_client;
 pub use winit::{
     self,
     event::{
-        ElementState, Event, KeyboardInput, MouseScrollDelta, VirtualKeyCode,
-        WindowEvent,
+        DeviceEvent, ElementState, Event, KeyboardInput, MouseScrollDelta,
+        VirtualKeyCode, WindowEvent,
     },
     window::Window,
 };
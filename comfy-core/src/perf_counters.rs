@@ -6,6 +6,82 @@ static PERF_COUNTERS: Lazy<AtomicRefCell<PerfCounters>> =
 static TIMINGS: Lazy<AtomicRefCell<Timings>> =
     Lazy::new(|| AtomicRefCell::new(Timings::new()));
 
+static STAGE_TIMINGS: Lazy<Mutex<StageTimings>> =
+    Lazy::new(|| Mutex::new(StageTimings::default()));
+
+/// How long each part of the last frame took, recorded while
+/// `GameConfig::profile_stages` is on.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct StageTimings {
+    /// Asset queues, text, camera and the other engine stages that run
+    /// before `GameLoop::update`.
+    pub early_update: Duration,
+    /// `GameLoop::update`, its fixed and rate limited variants, and
+    /// `GameLoop::render_update`.
+    pub game_update: Duration,
+    /// Sprites, trails, despawning and drawing the frame, including
+    /// `GameLoop::pre_render`/`post_render` and egui.
+    pub late_update: Duration,
+}
+
+impl StageTimings {
+    pub fn total(&self) -> Duration {
+        self.early_update + self.game_update + self.late_update
+    }
+}
+
+/// Per stage timings of the last frame, all zero unless
+/// `GameConfig::profile_stages` is on. Cheap enough to show in an overlay
+/// every frame.
+pub fn stage_timings() -> StageTimings {
+    *STAGE_TIMINGS.lock()
+}
+
+/// Measures the stages of a frame for `stage_timings()`. Doesn't read the
+/// clock at all when `GameConfig::profile_stages` is off.
+pub struct StageTimer {
+    last: Option<Instant>,
+    timings: StageTimings,
+}
+
+impl StageTimer {
+    pub fn start() -> Self {
+        Self {
+            last: game_config().profile_stages.then(Instant::now),
+            timings: StageTimings::default(),
+        }
+    }
+
+    fn lap(&mut self) -> Duration {
+        match self.last.as_mut() {
+            Some(last) => {
+                let now = Instant::now();
+                let elapsed = now - *last;
+                *last = now;
+                elapsed
+            }
+            None => Duration::ZERO,
+        }
+    }
+
+    pub fn early_update_done(&mut self) {
+        self.timings.early_update = self.lap();
+    }
+
+    pub fn game_update_done(&mut self) {
+        self.timings.game_update = self.lap();
+    }
+
+    /// Publishes the timings, leaves the previous frame's values in place
+    /// when profiling is off.
+    pub fn late_update_done(mut self) {
+        if self.last.is_some() {
+            self.timings.late_update = self.lap();
+            *STAGE_TIMINGS.lock() = self.timings;
+        }
+    }
+}
+
 pub struct TimingEntry {
     pub history: egui::util::History<Duration>,
     pub time: Instant,
@@ -35,6 +111,11 @@ impl Timings {
         entry.history.add(get_time(), value);
     }
 
+    /// The most recent value recorded for `name`.
+    pub fn latest(&self, name: &str) -> Option<Duration> {
+        self.data.get(name).and_then(|entry| entry.history.latest())
+    }
+
     pub fn span(&mut self, name: &'static str) -> TimingGuard<'_> {
         TimingGuard { timings: self, name, start: Instant::now() }
     }
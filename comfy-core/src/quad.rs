@@ -50,10 +50,18 @@ pub fn sin_range(offset: f32, speed: f32, min: f32, max: f32) -> f32 {
         ((speed * (get_time() as f32 + offset)).sin() / 2.0 + 0.5)
 }
 
+/// Overrides the clear color for the current frame only, it goes back to
+/// `GameConfig::clear_color` at the start of the next frame.
 pub fn clear_background(color: Color) {
     GLOBAL_STATE.borrow_mut().clear_color = color;
 }
 
+/// The color the current frame will be cleared to, i.e. the last
+/// `clear_background` of this frame or `GameConfig::clear_color`.
+pub fn current_clear_color() -> Color {
+    GLOBAL_STATE.borrow().clear_color
+}
+
 pub fn draw_quad(
     position: Vec2,
     size: Vec2,
@@ -19,6 +19,12 @@ pub fn srand(seed: u64) {
     rand();
 }
 
+/// Reseeds comfy's global RNG, e.g. to replay a section of a game
+/// deterministically. Same as `srand`.
+pub fn set_rng_seed(seed: u64) {
+    srand(seed);
+}
+
 /// returns a pseudo-random number in the range of 0 to u32::MAX.
 pub fn rand() -> u32 {
     let oldstate: u64 = STATE.load(Ordering::Relaxed);
@@ -0,0 +1,152 @@
+use crate::*;
+
+/// Physical key position as reported by the OS, independent of the keyboard
+/// layout. Binding movement to the scancode of W/A/S/D puts it on Z/Q/S/D
+/// for AZERTY players without any remapping.
+///
+/// The raw values differ between platforms, use `ScanCode::from_qwerty` to
+/// get the scancode of a key by where it sits on a US QWERTY keyboard.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(transparent)]
+pub struct ScanCode(pub u32);
+
+impl ScanCode {
+    /// The scancode of the key that produces `key` on a US QWERTY layout,
+    /// e.g. `ScanCode::from_qwerty(KeyCode::W)` is the key above S on any
+    /// layout.
+    ///
+    /// Only covers the letters, the number row, space, tab, escape and the
+    /// left shift and control keys. Returns `None` for other keys and on the
+    /// web, where scancodes aren't reported consistently.
+    pub fn from_qwerty(key: KeyCode) -> Option<ScanCode> {
+        cfg_if! {
+            if #[cfg(target_arch = "wasm32")] {
+                let _ = key;
+                None
+            } else if #[cfg(target_os = "macos")] {
+                mac_scancode(key).map(ScanCode)
+            } else {
+                // Windows uses set 1 scancodes and Linux evdev codes, which
+                // are the same for these keys.
+                pc_scancode(key).map(ScanCode)
+            }
+        }
+    }
+}
+
+pub fn is_scancode_pressed(scancode: ScanCode) -> bool {
+    GLOBAL_STATE.borrow().just_pressed_scancodes.contains(&scancode)
+}
+
+pub fn is_scancode_released(scancode: ScanCode) -> bool {
+    GLOBAL_STATE.borrow().just_released_scancodes.contains(&scancode)
+}
+
+pub fn is_scancode_down(scancode: ScanCode) -> bool {
+    GLOBAL_STATE.borrow().pressed_scancodes.contains(&scancode)
+}
+
+#[cfg(not(any(target_arch = "wasm32", target_os = "macos")))]
+fn pc_scancode(key: KeyCode) -> Option<u32> {
+    Some(match key {
+        KeyCode::Escape => 0x01,
+        KeyCode::Num1 => 0x02,
+        KeyCode::Num2 => 0x03,
+        KeyCode::Num3 => 0x04,
+        KeyCode::Num4 => 0x05,
+        KeyCode::Num5 => 0x06,
+        KeyCode::Num6 => 0x07,
+        KeyCode::Num7 => 0x08,
+        KeyCode::Num8 => 0x09,
+        KeyCode::Num9 => 0x0A,
+        KeyCode::Num0 => 0x0B,
+        KeyCode::Tab => 0x0F,
+        KeyCode::Q => 0x10,
+        KeyCode::W => 0x11,
+        KeyCode::E => 0x12,
+        KeyCode::R => 0x13,
+        KeyCode::T => 0x14,
+        KeyCode::Y => 0x15,
+        KeyCode::U => 0x16,
+        KeyCode::I => 0x17,
+        KeyCode::O => 0x18,
+        KeyCode::P => 0x19,
+        KeyCode::LCtrl => 0x1D,
+        KeyCode::A => 0x1E,
+        KeyCode::S => 0x1F,
+        KeyCode::D => 0x20,
+        KeyCode::F => 0x21,
+        KeyCode::G => 0x22,
+        KeyCode::H => 0x23,
+        KeyCode::J => 0x24,
+        KeyCode::K => 0x25,
+        KeyCode::L => 0x26,
+        KeyCode::LShift => 0x2A,
+        KeyCode::Z => 0x2C,
+        KeyCode::X => 0x2D,
+        KeyCode::C => 0x2E,
+        KeyCode::V => 0x2F,
+        KeyCode::B => 0x30,
+        KeyCode::N => 0x31,
+        KeyCode::M => 0x32,
+        KeyCode::Space => 0x39,
+        _ => return None,
+    })
+}
+
+#[cfg(all(target_os = "macos", not(target_arch = "wasm32")))]
+fn mac_scancode(key: KeyCode) -> Option<u32> {
+    Some(match key {
+        KeyCode::A => 0x00,
+        KeyCode::S => 0x01,
+        KeyCode::D => 0x02,
+        KeyCode::F => 0x03,
+        KeyCode::H => 0x04,
+        KeyCode::G => 0x05,
+        KeyCode::Z => 0x06,
+        KeyCode::X => 0x07,
+        KeyCode::C => 0x08,
+        KeyCode::V => 0x09,
+        KeyCode::B => 0x0B,
+        KeyCode::Q => 0x0C,
+        KeyCode::W => 0x0D,
+        KeyCode::E => 0x0E,
+        KeyCode::R => 0x0F,
+        KeyCode::Y => 0x10,
+        KeyCode::T => 0x11,
+        KeyCode::Num1 => 0x12,
+        KeyCode::Num2 => 0x13,
+        KeyCode::Num3 => 0x14,
+        KeyCode::Num4 => 0x15,
+        KeyCode::Num6 => 0x16,
+        KeyCode::Num5 => 0x17,
+        KeyCode::Num9 => 0x19,
+        KeyCode::Num7 => 0x1A,
+        KeyCode::Num8 => 0x1C,
+        KeyCode::Num0 => 0x1D,
+        KeyCode::O => 0x1F,
+        KeyCode::U => 0x20,
+        KeyCode::I => 0x22,
+        KeyCode::P => 0x23,
+        KeyCode::L => 0x25,
+        KeyCode::J => 0x26,
+        KeyCode::K => 0x28,
+        KeyCode::N => 0x2D,
+        KeyCode::M => 0x2E,
+        KeyCode::Tab => 0x30,
+        KeyCode::Space => 0x31,
+        KeyCode::Escape => 0x35,
+        KeyCode::LShift => 0x38,
+        KeyCode::LCtrl => 0x3B,
+        _ => return None,
+    })
+}
@@ -0,0 +1,230 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::*;
+
+/// Receives the size of the frame and its pixels as tightly packed RGBA8 rows.
+pub type ScreenshotCallback = Box<dyn FnOnce(UVec2, Vec<u8>) + Send>;
+
+pub enum ScreenshotRequest {
+    Png(PathBuf),
+    Bytes(ScreenshotCallback),
+}
+
+/// Saves the next rendered frame as a PNG at `path`.
+///
+/// The frame is read back from the GPU asynchronously and encoded on a
+/// background thread, so the file shows up a few frames later. Errors are
+/// logged.
+pub fn request_screenshot(path: impl AsRef<Path>) {
+    queue_command(EngineCommand::Screenshot(ScreenshotRequest::Png(
+        path.as_ref().to_path_buf(),
+    )));
+}
+
+/// Like `request_screenshot`, but hands the raw RGBA8 pixels of the next
+/// rendered frame to `callback` instead of writing a file. The callback runs
+/// on a background thread.
+pub fn request_screenshot_bytes(
+    callback: impl FnOnce(UVec2, Vec<u8>) + Send + 'static,
+) {
+    queue_command(EngineCommand::Screenshot(ScreenshotRequest::Bytes(
+        Box::new(callback),
+    )));
+}
+
+static FRAME_DUMP: Lazy<Mutex<Option<FrameDump>>> =
+    Lazy::new(|| Mutex::new(None));
+
+/// What to do when PNG encoding can't keep up with the frame rate during a
+/// frame dump.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum FrameDumpBackpressure {
+    /// Skips frames while too many are waiting to be written, the numbering
+    /// stays continuous so the dump still plays back smoothly, just with
+    /// fewer frames.
+    #[default]
+    DropFrames,
+    /// Blocks the game loop until there's room for the frame. Every frame is
+    /// kept, but the game slows down while dumping.
+    ///
+    /// Behaves like `DropFrames` on wasm, readbacks only finish once control
+    /// returns to the browser, so waiting for them would hang.
+    Throttle,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct FrameDumpConfig {
+    /// Captures every n-th rendered frame, `1` captures all of them.
+    pub every_n_frames: u64,
+    /// Stops the dump on its own after this many frames were captured.
+    pub max_frames: Option<u64>,
+    /// Number of frames that can be read back or encoded at the same time
+    /// before `backpressure` kicks in.
+    pub max_in_flight: usize,
+    pub backpressure: FrameDumpBackpressure,
+}
+
+impl Default for FrameDumpConfig {
+    fn default() -> Self {
+        Self {
+            every_n_frames: 1,
+            max_frames: None,
+            max_in_flight: 8,
+            backpressure: FrameDumpBackpressure::DropFrames,
+        }
+    }
+}
+
+struct FrameDump {
+    dir: PathBuf,
+    config: FrameDumpConfig,
+    start: Instant,
+    frames_seen: u64,
+    captured: u64,
+    dropped: u64,
+    in_flight: Arc<AtomicUsize>,
+    failed: Arc<AtomicUsize>,
+}
+
+/// Counts a captured frame as in flight until it's written, or until the
+/// request is dropped without running, e.g. because the readback failed.
+struct InFlight(Arc<AtomicUsize>);
+
+impl InFlight {
+    fn new(count: &Arc<AtomicUsize>) -> Self {
+        count.fetch_add(1, Ordering::SeqCst);
+        Self(count.clone())
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl FrameDump {
+    fn log_summary(&self) {
+        info!(
+            "Frame dump to {:?} finished: {} frames captured, {} dropped, {} \
+             failed to save, took {:.1}s",
+            self.dir,
+            self.captured,
+            self.dropped,
+            self.failed.load(Ordering::SeqCst),
+            self.start.elapsed().as_secs_f32(),
+        );
+    }
+}
+
+/// Starts writing rendered frames to `dir` as numbered PNGs
+/// (`frame-00000.png`, ...), e.g. for turning them into a video with
+/// `ffmpeg -i frame-%05d.png`. Replaces any dump in progress.
+///
+/// Frames go through the same readback as `request_screenshot` and are
+/// encoded on background threads, see `FrameDumpConfig::backpressure` for
+/// what happens when encoding falls behind.
+pub fn start_frame_dump(
+    dir: impl AsRef<Path>,
+    config: FrameDumpConfig,
+) -> Result<()> {
+    let dir = dir.as_ref().to_path_buf();
+    std::fs::create_dir_all(&dir)?;
+
+    stop_frame_dump();
+
+    *FRAME_DUMP.lock() = Some(FrameDump {
+        dir,
+        config,
+        start: Instant::now(),
+        frames_seen: 0,
+        captured: 0,
+        dropped: 0,
+        in_flight: Arc::new(AtomicUsize::new(0)),
+        failed: Arc::new(AtomicUsize::new(0)),
+    });
+
+    Ok(())
+}
+
+/// Stops the current frame dump and logs a summary. Frames that were already
+/// captured are still written.
+pub fn stop_frame_dump() {
+    if let Some(dump) = FRAME_DUMP.lock().take() {
+        dump.log_summary();
+    }
+}
+
+pub fn is_frame_dump_active() -> bool {
+    FRAME_DUMP.lock().is_some()
+}
+
+/// Called by the renderer once per presented frame. Returns the request to
+/// capture this frame with, if it's part of the dump.
+///
+/// With `FrameDumpBackpressure::Throttle`, `wait` is called repeatedly while
+/// the backlog is full. It has to make progress on the pending readbacks,
+/// e.g. by polling the device. It's never called on wasm.
+pub fn next_frame_dump_request(
+    mut wait: impl FnMut(),
+) -> Option<ScreenshotRequest> {
+    let mut guard = FRAME_DUMP.lock();
+    let dump = guard.as_mut()?;
+
+    let frame = dump.frames_seen;
+    dump.frames_seen += 1;
+
+    if frame % dump.config.every_n_frames.max(1) != 0 {
+        return None;
+    }
+
+    let max_in_flight = dump.config.max_in_flight.max(1);
+
+    if dump.in_flight.load(Ordering::SeqCst) >= max_in_flight {
+        match dump.config.backpressure {
+            FrameDumpBackpressure::DropFrames => {
+                dump.dropped += 1;
+                return None;
+            }
+            #[cfg(target_arch = "wasm32")]
+            FrameDumpBackpressure::Throttle => {
+                let _ = &mut wait;
+                dump.dropped += 1;
+                return None;
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            FrameDumpBackpressure::Throttle => {
+                while dump.in_flight.load(Ordering::SeqCst) >= max_in_flight {
+                    wait();
+                }
+            }
+        }
+    }
+
+    let path = dump.dir.join(format!("frame-{:05}.png", dump.captured));
+    dump.captured += 1;
+
+    let in_flight = InFlight::new(&dump.in_flight);
+    let failed = dump.failed.clone();
+
+    if dump.config.max_frames.is_some_and(|max| dump.captured >= max) {
+        if let Some(dump) = guard.take() {
+            dump.log_summary();
+        }
+    }
+
+    Some(ScreenshotRequest::Bytes(Box::new(move |size, pixels| {
+        let _in_flight = in_flight;
+
+        if let Err(err) = image::save_buffer(
+            &path,
+            &pixels,
+            size.x,
+            size.y,
+            image::ColorType::Rgba8,
+        ) {
+            error!("Failed to save frame to {:?}: {}", path, err);
+            failed.fetch_add(1, Ordering::SeqCst);
+        }
+    })))
+}
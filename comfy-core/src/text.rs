@@ -2,6 +2,7 @@ use std::sync::atomic::AtomicU64;
 
 use crate::*;
 
+#[derive(Clone)]
 pub struct DrawText {
     pub text: TextData,
     pub position: Vec2,
@@ -30,6 +31,7 @@ impl Default for TextParams {
 }
 
 #[doc(hidden)]
+#[derive(Clone)]
 pub enum TextData {
     Raw(String),
     Rich(RichText),
@@ -108,6 +110,12 @@ fn draw_text_internal(
     pro_params: Option<ProTextParams>,
     params: TextParams,
 ) {
+    assert!(
+        pro_params.is_some() || game_config().enable_egui,
+        "draw_text is drawn with egui, which is turned off with \
+         GameConfig::enable_egui. Use draw_text_pro_experimental instead."
+    );
+
     GLOBAL_STATE.borrow_mut().text_queue.push(DrawText {
         text,
         position,
@@ -120,6 +128,7 @@ fn draw_text_internal(
 
 #[doc(hidden)]
 /// Temporary while the API stabilizes.
+#[derive(Clone)]
 pub struct ProTextParams {
     pub font: FontHandle,
     pub font_size: f32,
@@ -147,6 +156,7 @@ pub struct StyledGlyph {
     pub color: Option<Color>,
 }
 
+#[derive(Clone)]
 pub struct RichText {
     pub clean_text: String,
     pub styled_glyphs: Vec<StyledGlyph>,
@@ -0,0 +1,47 @@
+use crate::*;
+
+static EVENT_LOOP_PROXY: Lazy<
+    Mutex<Option<winit::event_loop::EventLoopProxy<ComfyUserEvent>>>,
+> = Lazy::new(|| Mutex::new(None));
+
+/// Event sent to the event loop from another thread through
+/// `event_loop_proxy()`. Delivered to `GameLoop::on_user_event`.
+pub enum ComfyUserEvent {
+    /// Only wakes up the loop, e.g. so `LoopMode::Reactive` runs a frame
+    /// after a background task stored its result somewhere.
+    Wake,
+    /// Arbitrary payload, `downcast` it back in `on_user_event`.
+    Message(Box<dyn Any + Send>),
+    /// Sent by `schedule_redraw` while the loop is asleep so it picks up the
+    /// new wake up time. Doesn't run a frame by itself and isn't passed to
+    /// `on_user_event`.
+    Reschedule,
+}
+
+impl std::fmt::Debug for ComfyUserEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Wake => write!(f, "Wake"),
+            Self::Message(_) => write!(f, "Message(..)"),
+            Self::Reschedule => write!(f, "Reschedule"),
+        }
+    }
+}
+
+/// Handle for sending `ComfyUserEvent`s to the event loop from any thread.
+/// Every event wakes the loop up and runs a frame, also in
+/// `LoopMode::Reactive`.
+///
+/// Returns `None` before the event loop was created, and when running
+/// headless.
+pub fn event_loop_proxy(
+) -> Option<winit::event_loop::EventLoopProxy<ComfyUserEvent>> {
+    EVENT_LOOP_PROXY.lock().clone()
+}
+
+/// Called by the engine once the event loop exists.
+pub fn set_event_loop_proxy(
+    proxy: winit::event_loop::EventLoopProxy<ComfyUserEvent>,
+) {
+    *EVENT_LOOP_PROXY.lock() = Some(proxy);
+}
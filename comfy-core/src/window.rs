@@ -0,0 +1,1048 @@
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use crate::*;
+
+static NEXT_WINDOW_ID: AtomicU32 = AtomicU32::new(1);
+
+static REDRAW_AT: Lazy<Mutex<Option<Instant>>> = Lazy::new(|| Mutex::new(None));
+static LOOP_WAITING: AtomicBool = AtomicBool::new(false);
+
+static WINDOW_EGUI: Lazy<Mutex<HashMap<WindowId, egui::Context>>> =
+    Lazy::new(|| Mutex::new(HashMap::default()));
+
+static MONITORS: Lazy<Mutex<MonitorList>> =
+    Lazy::new(|| Mutex::new(MonitorList::default()));
+
+#[derive(Default)]
+struct MonitorList {
+    monitors: Vec<MonitorInfo>,
+    current: Option<MonitorId>,
+}
+
+/// The cursor grab mode that is actually in effect, which may differ from
+/// what was requested when the platform doesn't support locking the cursor.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum CursorGrabMode {
+    #[default]
+    None,
+    /// The cursor can't leave the window, but can still move inside it.
+    Confined,
+    /// The cursor is locked in place. Use `mouse_delta()` to read motion.
+    Locked,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PresentMode {
+    /// Vsync, frames wait for the display. Supported everywhere.
+    Fifo,
+    /// No vsync, frames are presented right away and may tear.
+    Immediate,
+    /// Low latency vsync without tearing, older frames are replaced.
+    Mailbox,
+}
+
+/// Switches the surface present mode, e.g. for a vsync toggle in a settings
+/// menu. Falls back to `PresentMode::Fifo` when the mode is unsupported.
+///
+/// Frame pacing is otherwise done on the CPU by sleeping to reach
+/// `target_framerate`. With `Fifo` the display is already limiting the
+/// framerate, so enable `GameConfig::fifo_disables_frame_limiter` to skip
+/// the sleep and avoid limiting twice. Applied at the start of the next frame.
+pub fn set_present_mode(mode: PresentMode) {
+    queue_command(EngineCommand::SetPresentMode(mode));
+}
+
+/// The present mode last applied with `set_present_mode`, `None` if it was
+/// never changed from the one picked at startup based on `vsync_enabled`.
+pub fn present_mode() -> Option<PresentMode> {
+    GLOBAL_STATE.borrow().present_mode
+}
+
+/// Appends the "(Comfy Engine DEV BUILD)" suffix to `title` in dev builds or
+/// when `COMFY_DEV_TITLE` is set.
+pub fn window_title_with_dev_suffix(title: &str) -> String {
+    let dev_title = format!("{} (Comfy Engine DEV BUILD)", title);
+
+    match std::env::var("COMFY_DEV_TITLE") {
+        Ok(_) => dev_title,
+        Err(_) => {
+            cfg_if! {
+                if #[cfg(feature = "dev")] {
+                    dev_title
+                } else {
+                    title.to_string()
+                }
+            }
+        }
+    }
+}
+
+/// Runs a frame as soon as possible when using `LoopMode::Reactive`, e.g.
+/// after the game state changed outside of input handling. Does nothing in
+/// `LoopMode::Continuous`, so it's fine to call every frame.
+pub fn request_redraw() {
+    schedule_redraw(Instant::now());
+}
+
+/// Like `schedule_redraw`, `delay` from now. Useful for showing the next
+/// step of an animation in `LoopMode::Reactive` without redrawing
+/// continuously.
+pub fn request_redraw_in(delay: Duration) {
+    if let Some(time) = Instant::now().checked_add(delay) {
+        schedule_redraw(time);
+    }
+}
+
+/// Makes sure a frame runs no later than `time` in `LoopMode::Reactive`.
+/// Earlier requests take precedence.
+///
+/// Can be called from any thread. If the loop is asleep waiting for events
+/// it's woken up to pick up the new time.
+pub fn schedule_redraw(time: Instant) {
+    let earlier = {
+        let mut redraw_at = REDRAW_AT.lock();
+        let earlier = !matches!(*redraw_at, Some(current) if current <= time);
+
+        if earlier {
+            *redraw_at = Some(time);
+        }
+
+        earlier
+    };
+
+    if earlier && LOOP_WAITING.load(Ordering::SeqCst) {
+        if let Some(proxy) = event_loop_proxy() {
+            let _ = proxy.send_event(ComfyUserEvent::Reschedule);
+        }
+    }
+}
+
+/// Called by the engine with `true` before the reactive loop goes to sleep
+/// and with `false` when it runs a frame.
+pub fn set_loop_waiting(waiting: bool) {
+    LOOP_WAITING.store(waiting, Ordering::SeqCst);
+}
+
+/// Time of the earliest pending redraw request. Clears the request when
+/// `take` is set, which the engine does when it starts a frame.
+pub fn next_redraw(take: bool) -> Option<Instant> {
+    let mut redraw_at = REDRAW_AT.lock();
+
+    if take {
+        redraw_at.take()
+    } else {
+        *redraw_at
+    }
+}
+
+/// Changes the window title, keeping the dev build suffix the startup title
+/// has (see `window_title_with_dev_suffix`). Applied at the start of the next
+/// frame.
+pub fn set_window_title(title: &str) {
+    set_window_title_raw(&window_title_with_dev_suffix(title));
+}
+
+/// Changes the window title to exactly `title`, without any suffix.
+pub fn set_window_title_raw(title: &str) {
+    queue_command(EngineCommand::SetTitle(title.to_string()));
+}
+
+/// Raw RGBA8 image used as the window icon.
+#[derive(Clone)]
+pub struct WindowIcon {
+    rgba: Vec<u8>,
+    width: u32,
+    height: u32,
+}
+
+impl WindowIcon {
+    /// Fails if `rgba` isn't exactly `width * height * 4` bytes.
+    pub fn from_rgba(rgba: Vec<u8>, width: u32, height: u32) -> Result<Self> {
+        let expected = width as usize * height as usize * 4;
+
+        if rgba.len() != expected {
+            bail!(
+                "Window icon of size {}x{} needs {} bytes of RGBA data, got {}",
+                width,
+                height,
+                expected,
+                rgba.len()
+            );
+        }
+
+        Ok(Self { rgba, width, height })
+    }
+
+    pub fn to_winit(&self) -> Option<winit::window::Icon> {
+        winit::window::Icon::from_rgba(
+            self.rgba.clone(),
+            self.width,
+            self.height,
+        )
+        .log_err_ok()
+    }
+}
+
+impl std::fmt::Debug for WindowIcon {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WindowIcon")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Cursor shown while the mouse is over the window, see
+/// `GameConfig::cursor_icon` and `set_cursor`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum CursorConfig {
+    /// The platform's default arrow.
+    #[default]
+    Default,
+    /// One of the standard cursors, e.g. `CursorIcon::Crosshair`. On wasm
+    /// these map to the matching CSS cursor.
+    Icon(winit::window::CursorIcon),
+    /// A cursor image. Only supported on wasm for now, other platforms log a
+    /// warning and show the default arrow.
+    Custom(CustomCursor),
+}
+
+/// Raw RGBA8 image used as the cursor, see `CursorConfig::Custom`.
+#[derive(Clone, PartialEq, Eq)]
+pub struct CustomCursor {
+    rgba: Vec<u8>,
+    size: UVec2,
+    hotspot: UVec2,
+}
+
+impl CustomCursor {
+    /// Browsers ignore cursor images larger than this.
+    pub const MAX_SIZE: u32 = 128;
+
+    /// `hotspot` is the pixel that clicks happen at, from the top left. Fails
+    /// if `rgba` isn't exactly `size.x * size.y * 4` bytes, the image is
+    /// larger than `MAX_SIZE` or the hotspot is outside of it.
+    pub fn from_rgba(
+        rgba: Vec<u8>,
+        size: UVec2,
+        hotspot: UVec2,
+    ) -> Result<Self> {
+        if size.x == 0 || size.y == 0 || size.max_element() > Self::MAX_SIZE {
+            bail!(
+                "Cursor images have to be between 1x1 and {}x{}, got {}x{}",
+                Self::MAX_SIZE,
+                Self::MAX_SIZE,
+                size.x,
+                size.y
+            );
+        }
+
+        let expected = size.x as usize * size.y as usize * 4;
+
+        if rgba.len() != expected {
+            bail!(
+                "Cursor of size {}x{} needs {} bytes of RGBA data, got {}",
+                size.x,
+                size.y,
+                expected,
+                rgba.len()
+            );
+        }
+
+        if hotspot.x >= size.x || hotspot.y >= size.y {
+            bail!(
+                "Cursor hotspot {:?} is outside of the {}x{} image",
+                hotspot,
+                size.x,
+                size.y
+            );
+        }
+
+        Ok(Self { rgba, size, hotspot })
+    }
+}
+
+impl std::fmt::Debug for CustomCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CustomCursor")
+            .field("size", &self.size)
+            .field("hotspot", &self.hotspot)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Changes the cursor shown over the window. Applied at the start of the
+/// next frame.
+pub fn set_cursor(cursor: CursorConfig) {
+    queue_command(EngineCommand::SetCursor(cursor));
+}
+
+/// Shows `cursor` over `window`, falling back to the default arrow (and
+/// logging why) when the platform can't show a custom image. Called by the
+/// engine, use `set_cursor` instead.
+pub fn apply_cursor(window: &Window, cursor: &CursorConfig) {
+    use winit::window::CursorIcon;
+
+    match cursor {
+        CursorConfig::Default => window.set_cursor_icon(CursorIcon::Default),
+        CursorConfig::Icon(icon) => window.set_cursor_icon(*icon),
+        CursorConfig::Custom(custom) => {
+            if let Err(err) = set_custom_cursor(window, custom) {
+                warn!("Using the default cursor, {}", err);
+                window.set_cursor_icon(CursorIcon::Default);
+            }
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn set_custom_cursor(
+    window: &Window,
+    cursor: &CustomCursor,
+) -> std::result::Result<(), String> {
+    use image::ImageEncoder;
+    use winit::platform::web::WindowExtWebSys;
+
+    let mut png = Vec::new();
+
+    image::codecs::png::PngEncoder::new(&mut png)
+        .write_image(
+            &cursor.rgba,
+            cursor.size.x,
+            cursor.size.y,
+            image::ColorType::Rgba8,
+        )
+        .map_err(|err| format!("failed to encode the cursor: {}", err))?;
+
+    // `btoa` takes a string with one character per byte.
+    let binary = png.iter().map(|&byte| byte as char).collect::<String>();
+
+    let base64 = web_sys::window()
+        .ok_or("there's no browser window")?
+        .btoa(&binary)
+        .map_err(|_| "failed to encode the cursor")?;
+
+    window
+        .canvas()
+        .style()
+        .set_property(
+            "cursor",
+            &format!(
+                "url(data:image/png;base64,{}) {} {}, auto",
+                base64, cursor.hotspot.x, cursor.hotspot.y
+            ),
+        )
+        .map_err(|_| "the browser rejected the cursor image".to_string())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn set_custom_cursor(
+    _window: &Window,
+    _cursor: &CustomCursor,
+) -> std::result::Result<(), String> {
+    Err("custom cursor images aren't supported on this platform".to_string())
+}
+
+/// Replaces the window icon with the given RGBA8 image. Applied at the start
+/// of the next frame. Does nothing on wasm.
+pub fn set_window_icon(rgba: Vec<u8>, width: u32, height: u32) -> Result<()> {
+    let icon = WindowIcon::from_rgba(rgba, width, height)?;
+    queue_command(EngineCommand::SetWindowIcon(icon));
+
+    Ok(())
+}
+
+pub fn is_window_focused() -> bool {
+    GLOBAL_STATE.borrow().window_focused
+}
+
+/// Physical size of the window as of the last resize, see
+/// `GameLoop::on_resize`.
+pub fn window_size() -> UVec2 {
+    GLOBAL_STATE.borrow().window_size
+}
+
+/// Size and position of the main window as reported by the OS, all in
+/// physical pixels. Use `scale_factor` to convert to logical pixels.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct WindowGeometry {
+    /// Size of the area the game is drawn into.
+    pub inner_size: UVec2,
+    /// Size including the title bar and borders. Same as `inner_size` on
+    /// platforms without decorations, such as the web.
+    pub outer_size: UVec2,
+    /// Top left corner of the outer window on the desktop, `None` on
+    /// platforms that don't expose it, e.g. Wayland.
+    pub position: Option<IVec2>,
+    pub scale_factor: f32,
+}
+
+/// Geometry of the main window, updated whenever it's resized, moved or
+/// changes scale factor. Unlike `window_size()` the inner size is updated
+/// right away, before `GameLoop::on_resize` runs.
+pub fn window_geometry() -> WindowGeometry {
+    GLOBAL_STATE.borrow().window_geometry
+}
+
+/// Window size limits from `GameConfig`, in physical pixels at the current
+/// scale factor. Meant for settings menus offering window sizes.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct WindowConstraints {
+    pub min_size: UVec2,
+    pub max_size: Option<UVec2>,
+    pub aspect_ratio: Option<f32>,
+}
+
+impl WindowConstraints {
+    /// Whether the window can be resized to `size`.
+    pub fn allows(&self, size: UVec2) -> bool {
+        let fits_max = match self.max_size {
+            Some(max) => size.cmple(max).all(),
+            None => true,
+        };
+
+        let fits_ratio = match self.aspect_ratio {
+            Some(ratio) => fit_aspect_ratio(size, ratio) == size,
+            None => true,
+        };
+
+        size.cmpge(self.min_size).all() && fits_max && fits_ratio
+    }
+}
+
+pub fn window_constraints() -> WindowConstraints {
+    let config = game_config();
+    let scale_factor = scale_factor();
+
+    WindowConstraints {
+        min_size: config.min_resolution.to_physical(scale_factor),
+        max_size: config
+            .max_resolution
+            .map(|max| max.to_physical(scale_factor)),
+        aspect_ratio: config.aspect_ratio.filter(|ratio| *ratio > 0.0),
+    }
+}
+
+/// Same as `window_geometry().inner_size`.
+pub fn window_inner_size() -> UVec2 {
+    window_geometry().inner_size
+}
+
+/// Same as `window_geometry().outer_size`, useful for placing companion
+/// windows or OS-level overlays next to the game window.
+pub fn window_outer_size() -> UVec2 {
+    window_geometry().outer_size
+}
+
+/// Same as `window_geometry().position`.
+pub fn window_position() -> Option<IVec2> {
+    window_geometry().position
+}
+
+/// Re-queries `window_geometry()` from `window`. Called by the engine at
+/// startup and on resize, move and scale factor events.
+pub fn update_window_geometry(window: &Window) {
+    let inner = window.inner_size();
+    let outer = window.outer_size();
+
+    GLOBAL_STATE.borrow_mut().window_geometry = WindowGeometry {
+        inner_size: uvec2(inner.width, inner.height),
+        outer_size: uvec2(outer.width, outer.height),
+        position: window.outer_position().ok().map(|x| ivec2(x.x, x.y)),
+        scale_factor: window.scale_factor() as f32,
+    };
+}
+
+/// Refresh rate in Hz of the monitor `window` is currently on, `None` if the
+/// platform doesn't report it.
+pub fn monitor_refresh_rate(window: &Window) -> Option<f32> {
+    window
+        .current_monitor()
+        .and_then(|monitor| monitor.refresh_rate_millihertz())
+        .map(|millihertz| millihertz as f32 / 1000.0)
+}
+
+/// OS scale factor of the main window, i.e. physical pixels per logical
+/// pixel, see `GameLoop::on_scale_factor_changed`.
+pub fn scale_factor() -> f32 {
+    let scale_factor = GLOBAL_STATE.borrow().window_scale_factor;
+
+    if scale_factor > 0.0 {
+        scale_factor
+    } else {
+        1.0
+    }
+}
+
+/// Window size and scale factor of the current frame, for converting
+/// between physical pixels (`mouse_screen()`, `window_size()`) and logical
+/// pixels. Both are updated together on resize and scale factor changes, so
+/// they always agree with each other. Get it with `EngineContext::coords`.
+///
+/// Placing a button 16 logical pixels from the bottom right corner, which
+/// stays the same size on any display:
+///
+/// ```ignore
+/// let coords = c.coords();
+/// let size = vec2(120.0, 40.0);
+/// let top_left = coords.window_size_logical() - size - 16.0;
+/// let button = AABB::from_top_left(top_left, size);
+///
+/// if button.contains(coords.mouse_logical()) &&
+///     is_mouse_button_pressed(MouseButton::Left)
+/// {
+///     // clicked
+/// }
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Coords {
+    window_size: UVec2,
+    scale_factor: f32,
+    mouse_position: Vec2,
+}
+
+impl Coords {
+    pub fn current() -> Self {
+        let state = GLOBAL_STATE.borrow();
+
+        Self {
+            window_size: state.window_size,
+            scale_factor: scale_factor(),
+            mouse_position: state.mouse_position,
+        }
+    }
+
+    /// Same as `window_size()`.
+    pub fn window_size_physical(&self) -> UVec2 {
+        self.window_size
+    }
+
+    pub fn window_size_logical(&self) -> Vec2 {
+        self.to_logical(self.window_size.as_vec2())
+    }
+
+    /// OS scale factor of the window, i.e. physical pixels per logical
+    /// pixel. Doesn't include `ui_scale()`.
+    pub fn scale_factor(&self) -> f32 {
+        self.scale_factor
+    }
+
+    /// Same as `mouse_position_logical()`.
+    pub fn mouse_logical(&self) -> Vec2 {
+        self.to_logical(self.mouse_position)
+    }
+
+    pub fn to_logical(&self, physical: Vec2) -> Vec2 {
+        physical / self.scale_factor
+    }
+
+    pub fn to_physical(&self, logical: Vec2) -> Vec2 {
+        logical * self.scale_factor
+    }
+}
+
+/// Parts of the window covered by notches, rounded corners or system bars,
+/// as `(top, right, bottom, left)` in physical pixels. Inset HUD elements by
+/// these so they stay visible. Updated on startup and on every resize.
+///
+/// Only implemented on the web, where it reads the CSS
+/// `env(safe-area-inset-*)` values, which browsers only report with
+/// `viewport-fit=cover` in the viewport meta tag. Native Android and iOS
+/// builds aren't supported yet and always report zero like desktop does.
+pub fn safe_area_insets() -> (f32, f32, f32, f32) {
+    GLOBAL_STATE.borrow().safe_area_insets
+}
+
+/// Re-reads the safe area insets from the platform. Called by the engine
+/// after resizes.
+pub fn update_safe_area_insets() {
+    let insets = platform_safe_area_insets().unwrap_or_default();
+    GLOBAL_STATE.borrow_mut().safe_area_insets = insets;
+}
+
+#[cfg(target_arch = "wasm32")]
+fn platform_safe_area_insets() -> Option<(f32, f32, f32, f32)> {
+    // `env()` is only available to CSS, so an invisible element is padded
+    // by the insets and its computed padding read back.
+    let window = web_sys::window()?;
+    let document = window.document()?;
+    let body = document.body()?;
+
+    let probe = document.create_element("div").ok()?;
+    probe
+        .set_attribute(
+            "style",
+            "position: fixed; visibility: hidden; pointer-events: none; \
+             padding: env(safe-area-inset-top, 0px) \
+             env(safe-area-inset-right, 0px) env(safe-area-inset-bottom, 0px) \
+             env(safe-area-inset-left, 0px);",
+        )
+        .ok()?;
+    body.append_child(&probe).ok()?;
+
+    let insets =
+        window.get_computed_style(&probe).ok().flatten().map(|style| {
+            let scale = window.device_pixel_ratio() as f32;
+
+            let px = |name: &str| {
+                style
+                    .get_property_value(name)
+                    .ok()
+                    .and_then(|x| x.trim().trim_end_matches("px").parse().ok())
+                    .unwrap_or(0.0f32) *
+                    scale
+            };
+
+            (
+                px("padding-top"),
+                px("padding-right"),
+                px("padding-bottom"),
+                px("padding-left"),
+            )
+        });
+
+    probe.remove();
+
+    insets
+}
+
+// TODO: read the insets from the activity on Android and from
+// `UIView.safeAreaInsets` on iOS.
+#[cfg(not(target_arch = "wasm32"))]
+fn platform_safe_area_insets() -> Option<(f32, f32, f32, f32)> {
+    None
+}
+
+/// `true` while the window has a zero size, e.g. when it's minimized on
+/// Windows. Rendering is skipped in that state, the game still updates.
+pub fn is_window_minimized() -> bool {
+    GLOBAL_STATE.borrow().window_minimized
+}
+
+/// Index into the list of monitors reported by the platform, `MonitorId(0)`
+/// is the first one.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MonitorId(pub usize);
+
+/// Describes an exclusive fullscreen video mode. The closest matching mode
+/// the monitor supports is used.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct VideoMode {
+    pub size: UVec2,
+    /// Picks the highest available refresh rate when `None`.
+    pub refresh_rate_millihertz: Option<u32>,
+    /// Uses the monitor the window is currently on when `None`.
+    pub monitor: Option<MonitorId>,
+}
+
+/// A monitor as reported by the platform, see `available_monitors()`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MonitorInfo {
+    pub id: MonitorId,
+    pub name: Option<String>,
+    /// Top left corner on the desktop in physical pixels.
+    pub position: IVec2,
+    /// Physical size of the mode the monitor is currently in.
+    pub size: UVec2,
+    pub scale_factor: f32,
+    pub refresh_rate_millihertz: Option<u32>,
+    /// Modes that can be passed to `FullscreenMode::Exclusive` as is, largest
+    /// and fastest first.
+    pub video_modes: Vec<VideoMode>,
+}
+
+impl MonitorInfo {
+    fn new(id: MonitorId, monitor: &winit::monitor::MonitorHandle) -> Self {
+        let mut video_modes = monitor
+            .video_modes()
+            .map(|mode| {
+                VideoMode {
+                    size: uvec2(mode.size().width, mode.size().height),
+                    refresh_rate_millihertz: Some(
+                        mode.refresh_rate_millihertz(),
+                    ),
+                    monitor: Some(id),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // Modes that only differ in bit depth show up more than once.
+        video_modes.sort_by_key(|mode| {
+            std::cmp::Reverse((
+                mode.size.x,
+                mode.size.y,
+                mode.refresh_rate_millihertz,
+            ))
+        });
+        video_modes.dedup();
+
+        Self {
+            id,
+            name: monitor.name(),
+            position: ivec2(monitor.position().x, monitor.position().y),
+            size: uvec2(monitor.size().width, monitor.size().height),
+            scale_factor: monitor.scale_factor() as f32,
+            refresh_rate_millihertz: monitor.refresh_rate_millihertz(),
+            video_modes,
+        }
+    }
+
+    fn matches(&self, monitor: &winit::monitor::MonitorHandle) -> bool {
+        self.name == monitor.name() &&
+            self.position ==
+                ivec2(monitor.position().x, monitor.position().y) &&
+            self.size == uvec2(monitor.size().width, monitor.size().height)
+    }
+}
+
+/// Monitors connected when the game started or when the window last moved,
+/// e.g. for a graphics settings menu. The index of a monitor in the list is
+/// its `MonitorId`. Empty on platforms that don't report monitors.
+pub fn available_monitors() -> Vec<MonitorInfo> {
+    MONITORS.lock().monitors.clone()
+}
+
+/// The monitor the main window is on, as of the last time it moved.
+pub fn current_monitor() -> Option<MonitorInfo> {
+    let list = MONITORS.lock();
+    let MonitorId(index) = list.current?;
+    list.monitors.get(index).cloned()
+}
+
+/// Updates `available_monitors()` and `current_monitor()`. Called by the
+/// engine at startup and after the window moved, as the monitors can't be
+/// queried without a window. Video modes are only queried again when the
+/// set of monitors changed.
+pub fn refresh_monitors(window: &Window) {
+    let handles = window.available_monitors().collect::<Vec<_>>();
+    let current = window.current_monitor();
+
+    let mut list = MONITORS.lock();
+
+    let unchanged = list.monitors.len() == handles.len() &&
+        list.monitors.iter().zip(handles.iter()).all(|(x, y)| x.matches(y));
+
+    if !unchanged {
+        list.monitors = handles
+            .iter()
+            .enumerate()
+            .map(|(index, monitor)| MonitorInfo::new(MonitorId(index), monitor))
+            .collect();
+    }
+
+    list.current = current
+        .and_then(|current| handles.iter().position(|x| *x == current))
+        .map(MonitorId);
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum FullscreenMode {
+    #[default]
+    Windowed,
+    /// Borderless fullscreen on the given monitor, or the one the window is
+    /// currently on when `None`.
+    Borderless(Option<MonitorId>),
+    /// Exclusive fullscreen, falls back to borderless if the monitor has no
+    /// video mode with the requested size.
+    Exclusive(VideoMode),
+}
+
+/// Switches the window between windowed and fullscreen modes. Applied at the
+/// start of the next frame, the resulting resize is handled by the engine
+/// like any other window resize.
+pub fn set_fullscreen(mode: FullscreenMode) {
+    queue_command(EngineCommand::SetFullscreen(mode));
+}
+
+/// State of the main window when it's first created, see
+/// `GameConfig::window_state`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum WindowStartState {
+    #[default]
+    Normal,
+    Maximized,
+    /// Stays hidden until shown with `set_window_visible(true)`.
+    Hidden,
+    /// Hidden until the first frame is presented, which avoids showing an
+    /// empty (often white) window while the game is starting up.
+    HiddenUntilFirstFrame,
+}
+
+/// Shows or hides the main window. Applied at the start of the next frame.
+pub fn set_window_visible(visible: bool) {
+    queue_command(EngineCommand::SetWindowVisible(visible));
+}
+
+/// Shows or hides the OS title bar and borders of the main window, see
+/// `GameConfig::decorations`. Applied at the start of the next frame.
+pub fn set_decorations(decorations: bool) {
+    queue_command(EngineCommand::SetDecorations(decorations));
+}
+
+/// Allows or prevents resizing the main window by dragging its edges.
+/// Applied at the start of the next frame.
+pub fn set_window_resizable(resizable: bool) {
+    queue_command(EngineCommand::SetResizable(resizable));
+}
+
+/// Starts moving the main window with the mouse, for custom title bars on
+/// windows without decorations. Call it when the left mouse button is
+/// pressed over the title bar, e.g. on `egui::Response::drag_started()`. The
+/// OS takes over until the button is released.
+///
+/// The drag starts at the beginning of the next frame. Most platforms only
+/// accept it while the button is still held, so calling it at any other time
+/// does nothing (on Wayland it can also fail, which is logged). Not
+/// supported on the web.
+pub fn start_window_drag() {
+    queue_command(EngineCommand::StartWindowDrag);
+}
+
+/// Requests the OS cursor to be grabbed (or released) by the window.
+///
+/// Tries to lock the cursor first and falls back to confining it to the
+/// window on platforms where locking isn't supported. The request is applied
+/// at the start of the next frame, check `cursor_grab_mode()` to see which
+/// mode ended up being used.
+pub fn set_cursor_grab(grab: bool) {
+    queue_command(EngineCommand::SetCursorGrab(grab));
+}
+
+/// Shows or hides the OS cursor while it's over the window. Applied at the
+/// start of the next frame.
+pub fn set_cursor_visible(visible: bool) {
+    queue_command(EngineCommand::SetCursorVisible(visible));
+}
+
+pub fn cursor_grab_mode() -> CursorGrabMode {
+    GLOBAL_STATE.borrow().cursor_grab_mode
+}
+
+/// Keeps the OS cursor inside `rect` (in physical window pixels, same as
+/// `mouse_screen()`), or lifts the restriction with `None`. Applied at the
+/// start of the next frame. The confinement is lifted on its own when the
+/// window loses focus, so the player can't get stuck.
+///
+/// Where the OS supports it the cursor is also confined to the window, and
+/// whenever it leaves `rect` it's moved back to the closest point inside.
+/// Moving the cursor isn't supported on Wayland and the web, there the
+/// cursor stays free (a warning is logged once).
+pub fn confine_cursor_to_rect(rect: Option<Rect>) {
+    queue_command(EngineCommand::ConfineCursor(rect));
+}
+
+/// The rect set by `confine_cursor_to_rect`, `None` if the cursor isn't
+/// confined.
+pub fn cursor_confine_rect() -> Option<Rect> {
+    GLOBAL_STATE.borrow().cursor_confine_rect
+}
+
+/// Applies `confine_cursor_to_rect`. Called by the engine.
+pub fn set_cursor_confine_rect(window: &Window, rect: Option<Rect>) {
+    use winit::window::CursorGrabMode as Mode;
+
+    let grab_mode = cursor_grab_mode();
+    GLOBAL_STATE.borrow_mut().cursor_confine_rect = rect;
+
+    // A grab from `set_cursor_grab` takes precedence.
+    if grab_mode == CursorGrabMode::None {
+        let mode = if rect.is_some() { Mode::Confined } else { Mode::None };
+
+        if let Err(err) = window.set_cursor_grab(mode) {
+            trace!("Cursor can't be confined to the window: {}", err);
+        }
+    }
+}
+
+/// Moves the cursor back inside the `confine_cursor_to_rect` rect after it
+/// moved to `position`. Returns where the cursor ends up. Called by the
+/// engine for every cursor movement.
+pub fn confine_cursor(window: &Window, position: Vec2) -> Vec2 {
+    static WARNED: AtomicBool = AtomicBool::new(false);
+
+    let Some(rect) = cursor_confine_rect() else {
+        return position;
+    };
+
+    let half_size = rect.size.abs() / 2.0;
+    let clamped =
+        position.clamp(rect.center - half_size, rect.center + half_size);
+
+    if clamped == position {
+        return position;
+    }
+
+    match window.set_cursor_position(winit::dpi::PhysicalPosition::new(
+        clamped.x, clamped.y,
+    )) {
+        Ok(()) => clamped,
+        Err(err) => {
+            if !WARNED.swap(true, Ordering::SeqCst) {
+                warn!("Can't keep the cursor inside the rect: {}", err);
+            }
+
+            position
+        }
+    }
+}
+
+/// Grabs or releases the cursor, returning the mode that ended up being used.
+pub fn grab_cursor(window: &Window, grab: bool) -> CursorGrabMode {
+    use winit::window::CursorGrabMode as Mode;
+
+    if !grab {
+        window.set_cursor_grab(Mode::None).log_err();
+        return CursorGrabMode::None;
+    }
+
+    if window.set_cursor_grab(Mode::Locked).is_ok() {
+        return CursorGrabMode::Locked;
+    }
+
+    match window.set_cursor_grab(Mode::Confined) {
+        Ok(()) => CursorGrabMode::Confined,
+        Err(err) => {
+            error!("Failed to grab the cursor: {}", err);
+            CursorGrabMode::None
+        }
+    }
+}
+
+fn find_monitor(
+    window: &Window,
+    id: Option<MonitorId>,
+) -> Option<winit::monitor::MonitorHandle> {
+    match id {
+        Some(MonitorId(index)) => {
+            let monitor = window.available_monitors().nth(index);
+
+            if monitor.is_none() {
+                warn!("Monitor {} not found, using the current one", index);
+                return window.current_monitor();
+            }
+
+            monitor
+        }
+        None => window.current_monitor(),
+    }
+}
+
+pub fn to_winit_fullscreen(
+    window: &Window,
+    mode: FullscreenMode,
+) -> Option<winit::window::Fullscreen> {
+    use winit::window::Fullscreen;
+
+    match mode {
+        FullscreenMode::Windowed => None,
+        FullscreenMode::Borderless(monitor) => {
+            Some(Fullscreen::Borderless(find_monitor(window, monitor)))
+        }
+        FullscreenMode::Exclusive(video_mode) => {
+            let monitor = find_monitor(window, video_mode.monitor);
+
+            let best = monitor.as_ref().and_then(|monitor| {
+                monitor
+                    .video_modes()
+                    .filter(|x| {
+                        x.size().width == video_mode.size.x &&
+                            x.size().height == video_mode.size.y
+                    })
+                    .max_by_key(|x| {
+                        let refresh = x.refresh_rate_millihertz();
+
+                        let refresh_score = match video_mode
+                            .refresh_rate_millihertz
+                        {
+                            Some(target) => u32::MAX - refresh.abs_diff(target),
+                            None => refresh,
+                        };
+
+                        (refresh_score, x.bit_depth())
+                    })
+            });
+
+            match best {
+                Some(best) => Some(Fullscreen::Exclusive(best)),
+                None => {
+                    warn!(
+                        "No video mode matching {:?}, using borderless",
+                        video_mode
+                    );
+                    Some(Fullscreen::Borderless(monitor))
+                }
+            }
+        }
+    }
+}
+
+/// Identifies a window created by the engine. The main game window is always
+/// `WindowId::PRIMARY`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WindowId(pub u32);
+
+impl WindowId {
+    pub const PRIMARY: WindowId = WindowId(0);
+}
+
+/// Settings for a secondary window created with `create_window`.
+#[derive(Clone, Debug)]
+pub struct WindowConfig {
+    pub title: String,
+    /// Size in logical pixels.
+    pub size: UVec2,
+    pub resizable: bool,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            title: "Comfy".to_string(),
+            size: uvec2(800, 600),
+            resizable: true,
+        }
+    }
+}
+
+/// Opens a secondary window, e.g. for an editor or debug tools. The window is
+/// created at the start of the next frame and drawn through
+/// `GameLoop::render_window` using its own egui context, see `window_egui`.
+///
+/// Closing a secondary window only closes that window, the game keeps
+/// running. Does nothing when running headless.
+pub fn create_window(config: WindowConfig) -> WindowId {
+    let id = WindowId(NEXT_WINDOW_ID.fetch_add(1, Ordering::SeqCst));
+    queue_command(EngineCommand::CreateWindow(id, config));
+    id
+}
+
+/// Closes a window created with `create_window`. Applied at the start of the
+/// next frame.
+pub fn close_window(id: WindowId) {
+    queue_command(EngineCommand::CloseWindow(id));
+}
+
+/// The egui context of the given window, `None` if the window doesn't exist
+/// (yet). For `WindowId::PRIMARY` this is the same as `egui()`.
+pub fn window_egui(id: WindowId) -> Option<egui::Context> {
+    if id == WindowId::PRIMARY {
+        return Some(egui().clone());
+    }
+
+    WINDOW_EGUI.lock().get(&id).cloned()
+}
+
+/// Tracks the egui context of a secondary window. Called by the engine when
+/// windows are created and closed.
+pub fn set_window_egui(id: WindowId, ctx: Option<egui::Context>) {
+    let mut contexts = WINDOW_EGUI.lock();
+
+    match ctx {
+        Some(ctx) => {
+            contexts.insert(id, ctx);
+        }
+        None => {
+            contexts.remove(&id);
+        }
+    }
+}
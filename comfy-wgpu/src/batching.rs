@@ -168,23 +168,36 @@ pub fn render_meshes(
     {
         let clear_color = if is_first { Some(clear_color) } else { None };
 
-        let target_view = if let Some(render_target) = pass_data.render_target {
-            &render_targets
-                .get(&render_target)
-                .expect("user render target must exist when used")
-                .view
-        } else if c.post_processing_effects.borrow().iter().any(|x| x.enabled) {
-            &c.first_pass_texture.texture.view
-        } else {
-            surface_view
-        };
+        let (target_view, resolve_target, depth_view) =
+            if let Some(render_target) = pass_data.render_target {
+                let view = &render_targets
+                    .get(&render_target)
+                    .expect("user render target must exist when used")
+                    .view;
+
+                (view, None, &c.depth_texture.view)
+            } else if let Some(msaa) = c.msaa.as_ref() {
+                (
+                    &msaa.color,
+                    Some(&c.first_pass_texture.texture.view),
+                    &msaa.depth,
+                )
+            } else if c.renders_to_first_pass() {
+                (
+                    &c.first_pass_texture.texture.view,
+                    None,
+                    &c.depth_texture.view,
+                )
+            } else {
+                (surface_view, None, &c.depth_texture.view)
+            };
 
         let mut render_pass =
             encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Mesh Render Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
                     view: target_view,
-                    resolve_target: None,
+                    resolve_target,
                     ops: wgpu::Operations {
                         load: color_to_clear_op(clear_color),
                         store: wgpu::StoreOp::Store,
@@ -192,7 +205,7 @@ pub fn render_meshes(
                 })],
                 depth_stencil_attachment: depth_stencil_attachment(
                     c.enable_z_buffer,
-                    &c.depth_texture.view,
+                    depth_view,
                     is_first,
                 ),
                 timestamp_writes: None,
@@ -273,19 +286,23 @@ pub fn render_particles(
 ) {
     let _span = span!("render_particles");
 
-    let target_view =
-        if c.post_processing_effects.borrow().iter().any(|x| x.enabled) {
-            &c.first_pass_texture.texture.view
+    let (target_view, resolve_target, depth_view) =
+        if let Some(msaa) = c.msaa.as_ref() {
+            (&msaa.color, Some(&c.first_pass_texture.texture.view), &msaa.depth)
+        } else if c.renders_to_first_pass() {
+            (&c.first_pass_texture.texture.view, None, &c.depth_texture.view)
         } else {
-            surface_view
+            (surface_view, None, &c.depth_texture.view)
         };
 
+    let sample_count = c.scene_sample_count();
+
     let textures = c.textures.lock();
 
     let particle_pipeline = {
         let name = format!(
-            "Particle {:?} {:?}",
-            pass_data.blend_mode, c.enable_z_buffer
+            "Particle {:?} {:?} {}",
+            pass_data.blend_mode, c.enable_z_buffer, sample_count
         );
 
         c.pipelines.entry(name.clone()).or_insert_with(|| {
@@ -299,6 +316,7 @@ pub fn render_particles(
                 &c.shaders.borrow().get(sprite_shader_id).unwrap().clone(),
                 pass_data.blend_mode,
                 c.enable_z_buffer,
+                sample_count,
             )
             .expect("particle pipeline creation failed")
         })
@@ -373,7 +391,7 @@ pub fn render_particles(
                 label: Some("Particle Render Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
                     view: target_view,
-                    resolve_target: None,
+                    resolve_target,
                     ops: wgpu::Operations {
                         load: color_to_clear_op(clear_color),
                         store: wgpu::StoreOp::Store,
@@ -391,7 +409,7 @@ pub fn render_particles(
                 // ),
                 depth_stencil_attachment: depth_stencil_attachment(
                     c.enable_z_buffer,
-                    &c.depth_texture.view,
+                    depth_view,
                     is_first,
                 ),
                 timestamp_writes: None,
@@ -4,8 +4,14 @@ use image::{GenericImageView, ImageBuffer};
 
 pub static BLOOD_CANVAS: OnceCell<AtomicRefCell<BloodCanvas>> = OnceCell::new();
 
+// The canvas is only created together with the renderer, when running
+// headless all of the functions below are no-ops.
 pub fn blood_canvas_update_and_draw(f: fn(IVec2, &CanvasBlock)) {
-    let mut canvas = BLOOD_CANVAS.get().unwrap().borrow_mut();
+    let Some(canvas) = BLOOD_CANVAS.get() else {
+        return;
+    };
+
+    let mut canvas = canvas.borrow_mut();
     let canvas = &mut *canvas;
 
     for (_, block) in canvas.blocks.iter_mut() {
@@ -26,7 +32,9 @@ pub fn blood_canvas_update_and_draw(f: fn(IVec2, &CanvasBlock)) {
 }
 
 pub fn blood_canvas_reset() {
-    BLOOD_CANVAS.get().unwrap().borrow_mut().blocks = HashMap::default();
+    if let Some(canvas) = BLOOD_CANVAS.get() {
+        canvas.borrow_mut().blocks = HashMap::default();
+    }
 }
 
 pub fn blood_circle_at(
@@ -35,11 +43,11 @@ pub fn blood_circle_at(
     pixel_prob: f32,
     color: fn() -> Color,
 ) {
-    BLOOD_CANVAS
-        .get()
-        .unwrap()
-        .borrow_mut()
-        .circle_at_internal(position, radius, pixel_prob, color);
+    if let Some(canvas) = BLOOD_CANVAS.get() {
+        canvas
+            .borrow_mut()
+            .circle_at_internal(position, radius, pixel_prob, color);
+    }
 }
 
 pub fn blood_canvas_blit_at(
@@ -48,12 +56,9 @@ pub fn blood_canvas_blit_at(
     source_rect: Option<IRect>,
     tint: Color,
 ) {
-    BLOOD_CANVAS.get().unwrap().borrow_mut().blit_at(
-        texture,
-        position,
-        source_rect,
-        tint,
-    );
+    if let Some(canvas) = BLOOD_CANVAS.get() {
+        canvas.borrow_mut().blit_at(texture, position, source_rect, tint);
+    }
 }
 
 // TODO: move this out of blood_canvas
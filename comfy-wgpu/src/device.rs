@@ -1,10 +1,217 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use wgpu_core::device::DeviceError;
+
 use crate::*;
 
+/// Why comfy couldn't start, see `try_run_comfy_main_async`.
+#[derive(Debug)]
+pub enum ComfyInitError {
+    WindowCreation(winit::error::OsError),
+    SurfaceCreation(wgpu::CreateSurfaceError),
+    /// No GPU (or driver) that can render to the window was found.
+    NoAdapter,
+    DeviceRequest(wgpu::RequestDeviceError),
+    /// The adapter can't present to the window, e.g. it reports no surface
+    /// formats.
+    UnsupportedSurface,
+    /// The window passed to `run_comfy_main_async_with_window` has a size of
+    /// zero.
+    ZeroSizedWindow,
+}
+
+impl std::fmt::Display for ComfyInitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::WindowCreation(err) => {
+                write!(f, "failed to create the window: {}", err)
+            }
+            Self::SurfaceCreation(err) => {
+                write!(f, "failed to create the window surface: {}", err)
+            }
+            Self::NoAdapter => f.write_str("no compatible GPU adapter found"),
+            Self::DeviceRequest(err) => {
+                write!(f, "failed to create the GPU device: {}", err)
+            }
+            Self::UnsupportedSurface => {
+                f.write_str("the GPU adapter can't present to the window")
+            }
+            Self::ZeroSizedWindow => {
+                f.write_str("the window has a size of zero")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ComfyInitError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::WindowCreation(err) => Some(err),
+            Self::SurfaceCreation(err) => Some(err),
+            Self::DeviceRequest(err) => Some(err),
+            Self::NoAdapter |
+            Self::UnsupportedSurface |
+            Self::ZeroSizedWindow => None,
+        }
+    }
+}
+
+static SOFTWARE_RENDERING: AtomicBool = AtomicBool::new(false);
+
+/// Whether the GPU adapter comfy picked is a software rasterizer such as
+/// lavapipe, llvmpipe or WARP. These work, but are usually far too slow for
+/// a game, so this can be used to show a warning or lower the quality.
+pub fn is_software_rendering() -> bool {
+    SOFTWARE_RENDERING.load(Ordering::SeqCst)
+}
+
+static GPU_INFO: Lazy<Mutex<Option<GpuInfo>>> = Lazy::new(|| Mutex::new(None));
+
+/// The GPU comfy renders with and what its device supports, see
+/// `gpu_info()`.
+#[derive(Clone, Debug)]
+pub struct GpuInfo {
+    pub name: String,
+    pub backend: wgpu::Backend,
+    pub device_type: wgpu::DeviceType,
+    pub driver: String,
+    pub driver_info: String,
+    /// Limits of the device comfy created, which can be lower than what the
+    /// adapter supports.
+    pub limits: wgpu::Limits,
+    pub features: wgpu::Features,
+}
+
+/// `None` until the renderer created its device, and when running headless.
+pub fn gpu_info() -> Option<GpuInfo> {
+    GPU_INFO.lock().clone()
+}
+
+/// Limits of the GPU device, e.g. `max_texture_dimension_2d` for picking
+/// texture sizes. Before the device exists these are the WebGL2 defaults,
+/// which every device comfy runs on supports.
+pub fn gpu_limits() -> wgpu::Limits {
+    GPU_INFO
+        .lock()
+        .as_ref()
+        .map(|info| info.limits.clone())
+        .unwrap_or_else(wgpu::Limits::downlevel_webgl2_defaults)
+}
+
+/// Optional features enabled on the GPU device, empty before the device
+/// exists.
+pub fn gpu_features() -> wgpu::Features {
+    GPU_INFO
+        .lock()
+        .as_ref()
+        .map(|info| info.features)
+        .unwrap_or_else(wgpu::Features::empty)
+}
+
+/// Whether a validation error was caused by the device being lost. Errors
+/// like `CreateBufferError` wrap `DeviceError` transparently, which hides it
+/// from `source()`, so those are unwrapped by their type.
+fn is_device_lost_error(err: &(dyn std::error::Error + 'static)) -> bool {
+    let mut source = Some(err);
+
+    while let Some(err) = source {
+        if matches!(device_error(err), Some(DeviceError::Lost)) {
+            return true;
+        }
+
+        source = err.source();
+    }
+
+    false
+}
+
+fn device_error<'a>(
+    err: &'a (dyn std::error::Error + 'static),
+) -> Option<&'a DeviceError> {
+    use wgpu_core::{
+        binding_model::{
+            CreateBindGroupError, CreateBindGroupLayoutError,
+            CreatePipelineLayoutError,
+        },
+        device::queue::{QueueSubmitError, QueueWriteError},
+        pipeline::{
+            CreateComputePipelineError, CreateRenderPipelineError,
+            CreateShaderModuleError,
+        },
+        present::{ConfigureSurfaceError, SurfaceError},
+        resource::{
+            BufferAccessError, CreateBufferError, CreateQuerySetError,
+            CreateSamplerError, CreateTextureError,
+        },
+    };
+
+    macro_rules! unwrap_device_error {
+        ($($ty:ident :: $variant:ident),* $(,)?) => {
+            $(
+                if let Some($ty::$variant(err)) = err.downcast_ref::<$ty>() {
+                    return Some(err);
+                }
+            )*
+        };
+    }
+
+    if let Some(err) = err.downcast_ref::<DeviceError>() {
+        return Some(err);
+    }
+
+    unwrap_device_error!(
+        BufferAccessError::Device,
+        CreateBufferError::Device,
+        CreateTextureError::Device,
+        CreateSamplerError::Device,
+        CreateQuerySetError::Device,
+        CreateBindGroupLayoutError::Device,
+        CreateBindGroupError::Device,
+        CreatePipelineLayoutError::Device,
+        CreateShaderModuleError::Device,
+        CreateRenderPipelineError::Device,
+        CreateComputePipelineError::Device,
+        SurfaceError::Device,
+        ConfigureSurfaceError::Device,
+        QueueWriteError::Queue,
+        QueueSubmitError::Queue,
+    );
+
+    None
+}
+
 pub async fn create_graphics_context(window: &Window) -> GraphicsContext {
+    try_create_graphics_context(window)
+        .await
+        .unwrap_or_else(|err| panic!("{}", err))
+}
+
+pub async fn try_create_graphics_context(
+    window: &Window,
+) -> Result<GraphicsContext, ComfyInitError> {
     let size = window.inner_size();
 
+    let gpu = game_config().gpu;
+
     let backends =
-        wgpu::util::backend_bits_from_env().unwrap_or(wgpu::Backends::all());
+        wgpu::util::backend_bits_from_env().unwrap_or(match gpu.backends {
+            GpuBackends::All => wgpu::Backends::all(),
+            GpuBackends::Primary => wgpu::Backends::PRIMARY,
+            GpuBackends::Vulkan => wgpu::Backends::VULKAN,
+            GpuBackends::Metal => wgpu::Backends::METAL,
+            GpuBackends::Dx12 => wgpu::Backends::DX12,
+            GpuBackends::Gl => wgpu::Backends::GL,
+        });
+
+    let power_preference = wgpu::util::power_preference_from_env().unwrap_or(
+        match gpu.power_preference {
+            PowerPreference::None => wgpu::PowerPreference::None,
+            PowerPreference::LowPower => wgpu::PowerPreference::LowPower,
+            PowerPreference::HighPerformance => {
+                wgpu::PowerPreference::HighPerformance
+            }
+        },
+    );
 
     let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
         // backends: wgpu::Backends::GL,
@@ -19,23 +226,38 @@ pub async fn create_graphics_context(window: &Window) -> GraphicsContext {
         gles_minor_version: wgpu::Gles3MinorVersion::Automatic,
     });
 
-    let surface = unsafe {
-        instance.create_surface(&window).expect("surface config must be valid")
-    };
+    let surface = unsafe { instance.create_surface(&window) }
+        .map_err(ComfyInitError::SurfaceCreation)?;
 
     trace!("Requesting adapter");
 
     let adapter = instance
         .request_adapter(&wgpu::RequestAdapterOptions {
-            // TODO: make this configurable
-            power_preference: wgpu::PowerPreference::HighPerformance,
+            power_preference,
             compatible_surface: Some(&surface),
-            force_fallback_adapter: false,
+            force_fallback_adapter: gpu.force_fallback,
         })
         .await
-        .expect("adapter config must be valid");
+        .ok_or(ComfyInitError::NoAdapter)?;
+
+    let info = adapter.get_info();
+    info!(
+        "Using adapter: {:?} ({:?}, {:?})",
+        info.name, info.backend, info.device_type
+    );
 
-    info!("Using adapter: {:?}", adapter.get_info().name);
+    let software = info.device_type == wgpu::DeviceType::Cpu;
+    SOFTWARE_RENDERING.store(software, Ordering::SeqCst);
+
+    if software && !gpu.force_fallback {
+        warn!(
+            "No hardware GPU adapter found, rendering in software on {:?}. \
+             Expect very low performance, updating the graphics drivers or \
+             picking a different backend with `GpuConfig::backends` or \
+             WGPU_BACKEND may help.",
+            info.name
+        );
+    }
 
     trace!("Requesting device");
 
@@ -54,14 +276,27 @@ pub async fn create_graphics_context(window: &Window) -> GraphicsContext {
     let (device, queue) = adapter
         .request_device(
             &wgpu::DeviceDescriptor {
-                features: wgpu::Features::empty(),
+                // Only 1 and 4 samples are guaranteed to work for MSAA, the
+                // other counts need this, see `choose_msaa_samples`.
+                features: adapter.features() &
+                    wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES,
                 limits,
                 label: None,
             },
             None,
         )
         .await
-        .expect("failed to create wgpu adapter");
+        .map_err(ComfyInitError::DeviceRequest)?;
+
+    *GPU_INFO.lock() = Some(GpuInfo {
+        name: info.name.clone(),
+        backend: info.backend,
+        device_type: info.device_type,
+        driver: info.driver.clone(),
+        driver_info: info.driver_info.clone(),
+        limits: device.limits(),
+        features: device.features(),
+    });
 
     #[cfg(fature = "ci-release")]
     device.on_uncaptured_error(Box::new(|err| {
@@ -69,7 +304,43 @@ pub async fn create_graphics_context(window: &Window) -> GraphicsContext {
         panic!("Exiting due to wgpu error: {:?}", err);
     }));
 
+    let device_lost = Arc::new(AtomicBool::new(false));
+
+    {
+        let device_lost = device_lost.clone();
+
+        // wgpu 0.18 has no device lost callback, a lost device shows up as
+        // errors from whatever uses it next. Unlike wgpu's default handler
+        // this doesn't panic when running out of memory or losing the
+        // device, everything else panics just like the default does.
+        device.on_uncaptured_error(Box::new(move |err| {
+            match &err {
+                wgpu::Error::OutOfMemory { .. } => {
+                    error!("GPU out of memory: {}", err);
+                }
+                wgpu::Error::Validation { source, .. }
+                    if is_device_lost_error(source.as_ref()) =>
+                {
+                    if !device_lost.swap(true, Ordering::SeqCst) {
+                        error!("GPU device lost: {}", err);
+                    }
+                }
+                wgpu::Error::Validation { .. } => {
+                    error!("Handling wgpu errors as fatal by default");
+                    panic!("wgpu error: {}\n", err);
+                }
+            }
+        }));
+    }
+
     let caps = surface.get_capabilities(&adapter);
+
+    if caps.formats.is_empty() ||
+        caps.present_modes.is_empty() ||
+        caps.alpha_modes.is_empty()
+    {
+        return Err(ComfyInitError::UnsupportedSurface);
+    }
     let supported_formats = caps.formats;
     info!("Supported formats: {:?}", supported_formats);
 
@@ -93,11 +364,12 @@ pub async fn create_graphics_context(window: &Window) -> GraphicsContext {
             fallback
         };
 
-    #[cfg(feature = "record-pngs")]
-    let surface_usage =
-        wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC;
-    #[cfg(not(feature = "record-pngs"))]
-    let surface_usage = wgpu::TextureUsages::RENDER_ATTACHMENT;
+    // COPY_SRC is needed to read back the frame for screenshots.
+    let surface_usage = if caps.usages.contains(wgpu::TextureUsages::COPY_SRC) {
+        wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC
+    } else {
+        wgpu::TextureUsages::RENDER_ATTACHMENT
+    };
 
     let desired_present_mode = if game_config().vsync_enabled {
         wgpu::PresentMode::AutoVsync
@@ -165,7 +437,7 @@ pub async fn create_graphics_context(window: &Window) -> GraphicsContext {
         device: device.clone(),
     }));
 
-    GraphicsContext {
+    Ok(GraphicsContext {
         surface: Arc::new(surface),
         instance: Arc::new(instance),
         adapter: Arc::new(adapter),
@@ -175,5 +447,34 @@ pub async fn create_graphics_context(window: &Window) -> GraphicsContext {
         config: Arc::new(AtomicRefCell::new(config)),
         texture_creator,
         textures,
+        device_lost,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wgpu_core::{error::ContextError, resource::CreateBufferError};
+
+    fn context_error(cause: CreateBufferError) -> ContextError {
+        ContextError {
+            string: "Device::create_buffer",
+            cause: Box::new(cause),
+            label_key: "label",
+            label: String::new(),
+        }
+    }
+
+    #[test]
+    fn detects_wrapped_device_lost() {
+        let lost = context_error(CreateBufferError::Device(DeviceError::Lost));
+        assert!(is_device_lost_error(&lost));
+
+        let out_of_memory =
+            context_error(CreateBufferError::Device(DeviceError::OutOfMemory));
+        assert!(!is_device_lost_error(&out_of_memory));
+
+        let invalid = context_error(CreateBufferError::UnalignedSize);
+        assert!(!is_device_lost_error(&invalid));
     }
 }
@@ -48,6 +48,7 @@ impl EguiRenderRoutine {
     pub fn end_frame_and_render(
         &mut self,
         ctx: &egui::Context,
+        output: egui::FullOutput,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         encoder: &mut wgpu::CommandEncoder,
@@ -55,10 +56,13 @@ impl EguiRenderRoutine {
         // view: &wgpu::TextureView,
         // render_pass: &'a mut wgpu::RenderPass<'a>,
     ) -> Vec<ClippedPrimitive> {
-        let egui::FullOutput { shapes, textures_delta, .. } = ctx.end_frame();
+        let egui::FullOutput { shapes, textures_delta, .. } = output;
 
         let paint_jobs = ctx.tessellate(shapes, pixels_per_point);
 
+        // Changes with `set_ui_scale` without the window being resized.
+        self.screen_descriptor.pixels_per_point = pixels_per_point;
+
         for id in textures_delta.free {
             self.render_pass.free_texture(&id);
         }
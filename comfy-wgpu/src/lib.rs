@@ -10,6 +10,7 @@ pub use winit::event::{
 };
 
 pub use fontdue;
+pub use raw_window_handle;
 
 mod batching;
 mod blood_canvas;
@@ -20,11 +21,13 @@ mod egui_integration;
 #[cfg(not(target_arch = "wasm32"))]
 mod hot_reload;
 mod instance;
+mod msaa;
 mod pipelines;
 mod post_processing;
 mod render_pass;
 mod renderer;
 mod screenshot;
+mod secondary_window;
 mod text;
 mod texture;
 mod utils;
@@ -39,10 +42,12 @@ pub use crate::egui_integration::*;
 #[cfg(not(target_arch = "wasm32"))]
 pub use crate::hot_reload::*;
 pub use crate::instance::*;
+pub use crate::msaa::*;
 pub use crate::pipelines::*;
 pub use crate::post_processing::*;
 pub use crate::render_pass::*;
 pub use crate::renderer::*;
+pub use crate::secondary_window::*;
 pub use crate::text::*;
 pub use crate::texture::*;
 pub use crate::utils::*;
@@ -340,6 +345,7 @@ pub fn create_render_pipeline(
     vertex_layouts: &[wgpu::VertexBufferLayout],
     shader: &Shader,
     blend_mode: BlendMode,
+    sample_count: u32,
 ) -> Result<wgpu::RenderPipeline> {
     // let module = naga::front::wgsl::parse_str(&shader.source)?;
     //
@@ -448,7 +454,7 @@ pub fn create_render_pipeline(
             }),
 
             multisample: wgpu::MultisampleState {
-                count: 1,
+                count: sample_count,
                 mask: !0,
                 alpha_to_coverage_enabled: false,
             },
@@ -467,6 +473,7 @@ pub fn create_render_pipeline_with_layout(
     shader: &Shader,
     blend_mode: BlendMode,
     enable_z_buffer: bool,
+    sample_count: u32,
 ) -> Result<wgpu::RenderPipeline> {
     let layout =
         device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
@@ -484,5 +491,6 @@ pub fn create_render_pipeline_with_layout(
         vertex_layouts,
         shader,
         blend_mode,
+        sample_count,
     )
 }
@@ -0,0 +1,107 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use crate::*;
+
+static MSAA_SAMPLES: AtomicU32 = AtomicU32::new(1);
+
+/// Sample count the scene is rendered with, `1` when MSAA is off. Can be
+/// lower than `GameConfig::msaa_samples` if the GPU doesn't support the
+/// requested count.
+pub fn msaa_samples() -> u32 {
+    MSAA_SAMPLES.load(Ordering::SeqCst)
+}
+
+/// Multisampled color and depth targets the scene is drawn into when MSAA is
+/// enabled. Every pass resolves into `WgpuRenderer::first_pass_texture`, so
+/// post processing and egui only ever see the resolved frame.
+pub struct MsaaTargets {
+    pub samples: u32,
+    pub color: wgpu::TextureView,
+    pub depth: wgpu::TextureView,
+}
+
+impl MsaaTargets {
+    /// Returns `None` for a sample count of `1`.
+    pub fn new(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        samples: u32,
+    ) -> Option<Self> {
+        if samples <= 1 {
+            return None;
+        }
+
+        let create_view = |label: &str, format: wgpu::TextureFormat| {
+            device
+                .create_texture(&wgpu::TextureDescriptor {
+                    label: Some(label),
+                    size: wgpu::Extent3d {
+                        width: width.max(1),
+                        height: height.max(1),
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: samples,
+                    dimension: wgpu::TextureDimension::D2,
+                    format,
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                    view_formats: &[],
+                })
+                .create_view(&wgpu::TextureViewDescriptor::default())
+        };
+
+        Some(Self {
+            samples,
+            color: create_view(
+                "MSAA Color Texture",
+                wgpu::TextureFormat::Rgba16Float,
+            ),
+            depth: create_view("MSAA Depth Texture", Texture::DEPTH_FORMAT),
+        })
+    }
+}
+
+/// Picks the sample count closest to `requested` that both the scene color
+/// format and the depth format support, preferring the lower one on a tie.
+/// The result is what `msaa_samples()` returns from then on.
+pub fn choose_msaa_samples(context: &GraphicsContext, requested: u32) -> u32 {
+    let requested = requested.max(1);
+
+    let device_features = context.device.features();
+
+    let supports = |format: wgpu::TextureFormat, count: u32| {
+        let features = if device_features
+            .contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES)
+        {
+            context.adapter.get_texture_format_features(format)
+        } else {
+            format.guaranteed_format_features(device_features)
+        };
+
+        features.flags.sample_count_supported(count)
+    };
+
+    let samples = [1, 2, 4, 8, 16]
+        .into_iter()
+        .filter(|&count| {
+            count == 1 ||
+                (supports(wgpu::TextureFormat::Rgba16Float, count) &&
+                    supports(Texture::DEPTH_FORMAT, count))
+        })
+        .min_by_key(|&count| (count.abs_diff(requested), count))
+        .unwrap_or(1);
+
+    if samples != requested {
+        warn!(
+            "MSAA with {} samples isn't supported, using {} instead",
+            requested, samples
+        );
+    } else if samples > 1 {
+        info!("Using MSAA with {} samples", samples);
+    }
+
+    MSAA_SAMPLES.store(samples, Ordering::SeqCst);
+
+    samples
+}
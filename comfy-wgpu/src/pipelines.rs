@@ -117,8 +117,15 @@ pub fn ensure_pipeline_exists(
         .as_ref()
         .and_then(|instance| shaders.get(instance.id));
 
+    // User render targets aren't multisampled.
+    let sample_count = if pass_data.render_target.is_some() {
+        1
+    } else {
+        c.scene_sample_count()
+    };
+
     let name = format!(
-        "{} {:?} {:?} {:?}",
+        "{} {:?} {:?} {:?} {}",
         if maybe_shader_instance.is_some() {
             "USER(Mesh)"
         } else {
@@ -126,7 +133,8 @@ pub fn ensure_pipeline_exists(
         },
         pass_data.blend_mode,
         maybe_shader,
-        c.enable_z_buffer
+        c.enable_z_buffer,
+        sample_count
     );
 
     let mesh_pipeline = if let Some(shader) = maybe_shader {
@@ -140,6 +148,7 @@ pub fn ensure_pipeline_exists(
                     &c.texture_layout,
                     &c.camera_bind_group_layout,
                     c.enable_z_buffer,
+                    sample_count,
                 )
             }),
         )
@@ -155,6 +164,7 @@ pub fn ensure_pipeline_exists(
                     shaders.get(sprite_shader_id).unwrap(),
                     pass_data.blend_mode,
                     c.enable_z_buffer,
+                    sample_count,
                 )
                 .unwrap()
             },
@@ -202,6 +212,7 @@ pub fn create_user_pipeline(
     texture_layout: &Arc<wgpu::BindGroupLayout>,
     camera_bind_group_layout: &wgpu::BindGroupLayout,
     enable_z_buffer: bool,
+    sample_count: u32,
 ) -> UserRenderPipeline {
     info!("Creating pipeline for shader: {:?}", shader.id);
 
@@ -285,6 +296,7 @@ pub fn create_user_pipeline(
         shader,
         pass_data.blend_mode,
         enable_z_buffer,
+        sample_count,
     )
     .unwrap();
 
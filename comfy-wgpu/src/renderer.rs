@@ -1,4 +1,7 @@
-use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{
+    atomic::{AtomicBool, AtomicU8, Ordering},
+    mpsc::{channel, Receiver, Sender},
+};
 
 use crate::*;
 
@@ -51,6 +54,8 @@ pub struct GraphicsContext {
     pub texture_creator: Arc<AtomicRefCell<WgpuTextureCreator>>,
     // TODO: atomic refcell?
     pub textures: Arc<Mutex<TextureMap>>,
+    /// Set once the device is lost, see `WgpuRenderer::is_device_lost`.
+    pub device_lost: Arc<AtomicBool>,
 }
 
 pub struct WgpuRenderer {
@@ -63,10 +68,27 @@ pub struct WgpuRenderer {
 
     pub text: RefCell<TextRasterizer>,
 
-    pub egui_winit: egui_winit::State,
+    /// `None` with `GameConfig::enable_egui` turned off.
+    pub egui_winit: Option<egui_winit::State>,
+    /// The icon `egui_winit` last set on the window, mirrors its own
+    /// bookkeeping so the game's cursor can be restored afterwards.
+    egui_cursor_icon: Option<egui::CursorIcon>,
     pub egui_render_routine: RefCell<EguiRenderRoutine>,
+    /// See `command_encoder`.
+    user_encoder: Option<wgpu::CommandEncoder>,
 
     pub screenshot_buffer: SizedBuffer,
+    /// Captured from the next drawn frame, see `request_screenshot`.
+    pub screenshot_requests: Vec<ScreenshotRequest>,
+    /// Makes the window visible after the next successful present, used for
+    /// `WindowStartState::HiddenUntilFirstFrame`.
+    pub show_window_after_present: bool,
+    pending_screenshots: Vec<screenshot::PendingScreenshot>,
+    /// Drawn by `draw`, shown by `present`.
+    frame: Option<(wgpu::SurfaceTexture, wgpu::TextureView)>,
+    /// Work submitted for the frames the GPU hasn't finished yet, see
+    /// `GameConfig::max_frame_latency`.
+    frames_in_flight: VecDeque<wgpu::SubmissionIndex>,
 
     pub vertex_buffer: SizedBuffer,
     pub index_buffer: SizedBuffer,
@@ -80,6 +102,8 @@ pub struct WgpuRenderer {
     pub depth_texture: Arc<Texture>,
 
     pub first_pass_texture: BindableTexture,
+    /// Set when `GameConfig::msaa_samples` is above `1`, see `MsaaTargets`.
+    pub msaa: Option<MsaaTargets>,
 
     pub lights_buffer: wgpu::Buffer,
     pub global_lighting_params_buffer: wgpu::Buffer,
@@ -91,6 +115,9 @@ pub struct WgpuRenderer {
 
     pub tonemapping_texture: BindableTexture,
 
+    /// Letterboxed area of the surface for `ResolutionConfig::Aspect`.
+    pub viewport: Option<Rect>,
+
     pub camera_uniform: CameraUniform,
     pub camera_buffer: wgpu::Buffer,
     pub camera_bind_group: Arc<wgpu::BindGroup>,
@@ -115,8 +142,35 @@ pub struct WgpuRenderer {
 }
 
 impl WgpuRenderer {
-    pub async fn new(window: Window, egui_winit: egui_winit::State) -> Self {
-        let context = create_graphics_context(&window).await;
+    pub async fn new(
+        window: Window,
+        egui_winit: Option<egui_winit::State>,
+    ) -> Self {
+        Self::try_new(window, egui_winit)
+            .await
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Like `new`, but returns an error instead of panicking when there's no
+    /// usable GPU.
+    pub async fn try_new(
+        window: Window,
+        egui_winit: Option<egui_winit::State>,
+    ) -> Result<Self, ComfyInitError> {
+        let context = try_create_graphics_context(&window).await?;
+
+        #[cfg(not(target_arch = "wasm32"))]
+        init_clipboard(&window);
+
+        if let Some(latency) = game_config().max_frame_latency {
+            if !(1..=3).contains(&latency) {
+                warn!(
+                    "max_frame_latency must be in 1..=3, got {}, using {}",
+                    latency,
+                    latency.clamp(1, 3)
+                );
+            }
+        }
 
         trace!("Loading builtin engine textures");
 
@@ -390,6 +444,13 @@ impl WgpuRenderer {
             },
         );
 
+        let msaa = MsaaTargets::new(
+            &context.device,
+            width,
+            height,
+            choose_msaa_samples(&context, game_config().msaa_samples),
+        );
+
         let tonemapping_texture = BindableTexture::new(
             &context.device,
             &context.texture_layout,
@@ -462,6 +523,10 @@ impl WgpuRenderer {
         )
         .unwrap();
 
+        // Set right away so that `viewport_rect()` is right during setup,
+        // before the first frame or resize.
+        let viewport = apply_viewport(uvec2(width, height));
+
         let renderer = Self {
             #[cfg(not(target_arch = "wasm32"))]
             thread_pool: rayon::ThreadPoolBuilder::new().build().unwrap(),
@@ -483,6 +548,11 @@ impl WgpuRenderer {
             render_targets: RefCell::new(HashMap::new()),
 
             screenshot_buffer,
+            screenshot_requests: Vec::new(),
+            show_window_after_present: false,
+            pending_screenshots: Vec::new(),
+            frame: None,
+            frames_in_flight: VecDeque::new(),
 
             vertex_buffer,
             index_buffer,
@@ -491,9 +561,12 @@ impl WgpuRenderer {
             bloom,
 
             egui_winit,
+            egui_cursor_icon: None,
+            user_encoder: None,
             egui_render_routine: RefCell::new(egui_render_routine),
 
             first_pass_texture,
+            msaa,
 
             lights_buffer,
 
@@ -505,6 +578,8 @@ impl WgpuRenderer {
 
             tonemapping_texture,
 
+            viewport,
+
             camera_uniform,
             camera_buffer,
             camera_bind_group,
@@ -536,13 +611,32 @@ impl WgpuRenderer {
             insert_post_processing_effect(&renderer, 0, "copy", copy_shader_id);
         }
 
-        renderer
+        Ok(renderer)
     }
 
     pub fn window(&self) -> &Window {
         &self.window
     }
 
+    /// Advanced: the platform handle of the main window, for libraries that
+    /// need to attach to it, e.g. native file dialogs or video players.
+    /// Libraries taking `impl HasRawWindowHandle` can use `window()` instead.
+    ///
+    /// The handle is only valid while the renderer is alive, it's recreated
+    /// along with the window after a lost device, so don't keep it around.
+    /// Native code must also leave alone whatever winit manages (the event
+    /// handling, size and visibility of the window, ...), that breaks winit's
+    /// assumptions in ways that are hard to debug.
+    pub fn raw_window_handle(&self) -> raw_window_handle::RawWindowHandle {
+        raw_window_handle::HasRawWindowHandle::raw_window_handle(&self.window)
+    }
+
+    /// Advanced: the platform display handle (e.g. the X11 or Wayland
+    /// connection) that goes with `raw_window_handle`, with the same caveats.
+    pub fn raw_display_handle(&self) -> raw_window_handle::RawDisplayHandle {
+        raw_window_handle::HasRawDisplayHandle::raw_display_handle(&self.window)
+    }
+
     pub fn render_post_processing(
         &mut self,
         screen_view: &wgpu::TextureView,
@@ -566,9 +660,12 @@ impl WgpuRenderer {
         let post_processing_effects = self.post_processing_effects.borrow();
         let surface_texture_format = self.context.config.borrow().format;
 
-        let (last_effect_view, last_effect_format) = if game_config
-            .tonemapping_enabled
-        {
+        // Letterboxing needs a final pass that draws into the viewport, same
+        // as tonemapping, so the effects render into an offscreen texture.
+        let has_final_pass =
+            game_config.tonemapping_enabled || self.viewport.is_some();
+
+        let (last_effect_view, last_effect_format) = if has_final_pass {
             (&self.tonemapping_texture.texture.view, self.render_texture_format)
         } else {
             (screen_view, surface_texture_format)
@@ -645,34 +742,54 @@ impl WgpuRenderer {
             );
         }
 
-        if game_config.tonemapping_enabled {
-            let tonemapping_pipeline = self
+        if has_final_pass {
+            let final_pipeline_name = if game_config.tonemapping_enabled {
+                "tonemapping"
+            } else {
+                "letterbox"
+            };
+
+            let final_pipeline = self
                 .pipelines
-                .entry("tonemapping".into())
+                .entry(final_pipeline_name.into())
                 .or_insert_with(|| {
                     // TODO: texture format?
                     let shaders = &mut self.shaders.borrow_mut();
 
+                    let shader = if game_config.tonemapping_enabled {
+                        create_engine_post_processing_shader!(
+                            shaders,
+                            "tonemapping"
+                        )
+                    } else {
+                        create_engine_post_processing_shader!(shaders, "copy")
+                    };
+
                     create_post_processing_pipeline(
-                        "Tonemapping",
+                        final_pipeline_name,
                         &self.context.device,
                         self.context.config.borrow().format,
                         &[&self.texture_layout, &self.camera_bind_group_layout],
-                        create_engine_post_processing_shader!(
-                            shaders,
-                            "tonemapping"
-                        ),
+                        shader,
                         wgpu::BlendState::REPLACE,
                     )
                 });
 
+            // Without any effects the scene is still in the first pass
+            // texture.
+            let final_input = if enabled_effects.is_empty() {
+                &self.first_pass_texture.bind_group
+            } else {
+                &self.tonemapping_texture.bind_group
+            };
 
             {
-                let should_clear = false;
+                // The black bars of the letterbox.
+                let should_clear = self.viewport.is_some();
 
                 let mut render_pass =
                     encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                        label: Some("Tonemapping"),
+                        label: Some(final_pipeline_name),
                         color_attachments: &[Some(
                             wgpu::RenderPassColorAttachment {
                                 view: screen_view,
@@ -697,12 +814,21 @@ impl WgpuRenderer {
                         occlusion_query_set: None,
                     });
 
-                render_pass.set_pipeline(tonemapping_pipeline);
-                render_pass.set_bind_group(
-                    0,
-                    &self.tonemapping_texture.bind_group,
-                    &[],
-                );
+                if let Some(viewport) = self.viewport {
+                    let min = viewport.top_left();
+
+                    render_pass.set_viewport(
+                        min.x,
+                        min.y,
+                        viewport.size.x,
+                        viewport.size.y,
+                        0.0,
+                        1.0,
+                    );
+                }
+
+                render_pass.set_pipeline(final_pipeline);
+                render_pass.set_bind_group(0, final_input, &[]);
                 render_pass.set_bind_group(1, &self.camera_bind_group, &[]);
 
                 render_pass.draw(0..3, 0..1);
@@ -712,7 +838,53 @@ impl WgpuRenderer {
         self.context.queue.submit(std::iter::once(encoder.finish()));
     }
 
-    pub fn render_egui(&self, view: &wgpu::TextureView, params: &DrawParams) {
+    /// Ends the egui frame and applies its platform output (cursor icon,
+    /// clipboard, opened URLs, IME) to the window, see `egui_output()`.
+    pub fn end_egui_frame(&mut self, egui: &egui::Context) -> egui::FullOutput {
+        let mut output = egui.end_frame();
+
+        let platform_output = std::mem::take(&mut output.platform_output);
+        let cursor_icon = platform_output.cursor_icon;
+
+        set_egui_output(platform_output.clone());
+
+        if let Some(egui_winit) = self.egui_winit.as_mut() {
+            egui_winit.handle_platform_output(
+                &self.window,
+                egui,
+                platform_output,
+            );
+        }
+
+        // `egui_winit` only touches the cursor when its icon changes while
+        // the pointer is over the window. Once it's back to the default,
+        // show the game's cursor again.
+        let changed = if egui.input(|i| i.pointer.hover_pos().is_some()) {
+            self.egui_cursor_icon.replace(cursor_icon) != Some(cursor_icon)
+        } else {
+            self.egui_cursor_icon = None;
+            false
+        };
+
+        if changed && cursor_icon == egui::CursorIcon::Default {
+            let state = GLOBAL_STATE.borrow();
+
+            apply_cursor(&self.window, &state.cursor);
+
+            if state.cursor_hidden {
+                self.window.set_cursor_visible(false);
+            }
+        }
+
+        output
+    }
+
+    pub fn render_egui(
+        &self,
+        view: &wgpu::TextureView,
+        egui: &egui::Context,
+        output: egui::FullOutput,
+    ) {
         let _span = span!("render_egui");
 
         let mut encoder =
@@ -720,7 +892,8 @@ impl WgpuRenderer {
 
         let paint_jobs =
             self.egui_render_routine.borrow_mut().end_frame_and_render(
-                params.egui,
+                egui,
+                output,
                 &self.context.device,
                 &self.context.queue,
                 &mut encoder,
@@ -748,7 +921,9 @@ impl WgpuRenderer {
         event: &winit::event::WindowEvent,
         egui_ctx: &egui::Context,
     ) -> bool {
-        self.egui_winit.on_window_event(egui_ctx, event).consumed
+        self.egui_winit
+            .as_mut()
+            .is_some_and(|x| x.on_window_event(egui_ctx, event).consumed)
     }
 
     pub fn as_mut_any(&mut self) -> &mut dyn Any {
@@ -760,7 +935,7 @@ impl WgpuRenderer {
 
         let mut changed_recording_mode = false;
 
-        if is_key_pressed(KeyCode::F3) {
+        if is_key_pressed_this_frame(KeyCode::F3) {
             params.config.dev.recording_mode =
                 match params.config.dev.recording_mode {
                     RecordingMode::None => RecordingMode::Landscape,
@@ -771,12 +946,12 @@ impl WgpuRenderer {
             changed_recording_mode = true;
         }
 
-        if is_key_pressed(KeyCode::F9) {
+        if is_key_pressed_this_frame(KeyCode::F9) {
             self.enable_z_buffer = !self.enable_z_buffer;
             info!("Z Buffer: {}", self.enable_z_buffer);
         }
 
-        if is_key_pressed(KeyCode::F4) {
+        if is_key_pressed_this_frame(KeyCode::F4) {
             params.config.dev.recording_mode =
                 match params.config.dev.recording_mode {
                     RecordingMode::None => RecordingMode::Tiktok,
@@ -904,15 +1079,34 @@ impl WgpuRenderer {
         );
     }
 
+    /// Draws the world and post processing into the next surface texture.
+    /// The frame isn't shown until `present`, in between the game can draw
+    /// into `frame_view()` itself.
     pub fn draw(&mut self, params: DrawParams) {
         span_with_timing!("render");
 
+        self.finish_screenshots();
+
         let output = {
             let _span = span!("get current surface");
 
             match self.context.surface.get_current_texture() {
                 Ok(texture) => texture,
-                Err(_) => {
+                Err(
+                    wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated,
+                ) => {
+                    // Skips the frame, the next one uses the new surface.
+                    self.context.surface.configure(
+                        &self.context.device,
+                        &self.context.config.borrow(),
+                    );
+                    return;
+                }
+                Err(wgpu::SurfaceError::OutOfMemory) => {
+                    error!("Out of memory getting the surface texture");
+                    return;
+                }
+                Err(wgpu::SurfaceError::Timeout) => {
                     return;
                 }
             }
@@ -932,9 +1126,46 @@ impl WgpuRenderer {
         );
 
         self.render_post_processing(&surface_view, params.config);
-        self.render_egui(&surface_view, &params);
 
-        if params.config.dev.show_buffers {
+        self.frame = Some((output, surface_view));
+    }
+
+    /// Encoder for custom passes recorded in `GameLoop::pre_render` and
+    /// `post_render`. It's submitted right after the hook returns, so work
+    /// recorded in `pre_render` runs before comfy's passes and work recorded
+    /// in `post_render` before egui.
+    pub fn command_encoder(&mut self) -> &mut wgpu::CommandEncoder {
+        self.user_encoder.get_or_insert_with(|| {
+            self.context.device.simple_encoder("User Render Encoder")
+        })
+    }
+
+    /// Submits the work recorded through `command_encoder`, if any.
+    pub fn submit_command_encoder(&mut self) {
+        if let Some(encoder) = self.user_encoder.take() {
+            self.context.queue.submit(std::iter::once(encoder.finish()));
+        }
+    }
+
+    /// The surface texture of the frame drawn by `draw`, until it's shown by
+    /// `present`. `None` if there's no frame, e.g. the surface was lost.
+    pub fn frame_view(&self) -> Option<&wgpu::TextureView> {
+        self.frame.as_ref().map(|(_, view)| view)
+    }
+
+    /// Draws egui on top of the frame drawn by `draw` and shows it.
+    pub fn present(&mut self, egui: &egui::Context, config: &GameConfig) {
+        let egui_output = config.enable_egui.then(|| self.end_egui_frame(egui));
+
+        let Some((output, surface_view)) = self.frame.take() else {
+            return;
+        };
+
+        if let Some(egui_output) = egui_output {
+            self.render_egui(&surface_view, egui, egui_output);
+        }
+
+        if config.dev.show_buffers {
             let pp = self.post_processing_effects.borrow();
 
             let mut bind_groups = vec![&self.first_pass_texture.bind_group];
@@ -972,7 +1203,75 @@ impl WgpuRenderer {
             );
         }
 
+        if let Some(request) = next_frame_dump_request(|| {
+            self.finish_screenshots();
+
+            #[cfg(not(target_arch = "wasm32"))]
+            std::thread::sleep(Duration::from_millis(1));
+        }) {
+            self.screenshot_requests.push(request);
+        }
+
+        if !self.screenshot_requests.is_empty() {
+            let requests = std::mem::take(&mut self.screenshot_requests);
+
+            if let Some(pending) = screenshot::capture_screenshot(
+                &self.context,
+                &output.texture,
+                requests,
+            ) {
+                self.pending_screenshots.push(pending);
+            }
+        }
+
         output.present();
+
+        if self.show_window_after_present {
+            self.show_window_after_present = false;
+            self.window.set_visible(true);
+        }
+
+        self.limit_frame_latency();
+    }
+
+    /// Blocks until the GPU is at most `max_frame_latency()` frames behind.
+    fn limit_frame_latency(&mut self) {
+        let Some(max_latency) = max_frame_latency() else {
+            return;
+        };
+
+        // An empty submit covers all the work submitted before it.
+        let index = self.context.queue.submit(std::iter::empty());
+        self.frames_in_flight.push_back(index);
+
+        while self.frames_in_flight.len() > max_latency as usize {
+            let Some(index) = self.frames_in_flight.pop_front() else {
+                break;
+            };
+
+            self.context
+                .device
+                .poll(wgpu::Maintain::WaitForSubmissionIndex(index));
+        }
+    }
+
+    fn finish_screenshots(&mut self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        let thread_pool = &self.thread_pool;
+
+        screenshot::finish_screenshots(
+            &self.context,
+            &mut self.pending_screenshots,
+            |task| {
+                cfg_if! {
+                    if #[cfg(target_arch = "wasm32")] {
+                        task();
+                    } else {
+                        thread_pool.spawn(task);
+                    }
+                }
+            },
+        );
     }
 
     pub fn scale_factor(&self) -> f32 {
@@ -1003,6 +1302,98 @@ impl WgpuRenderer {
         );
 
         // self.egui_winit.set_pixels_per_point(scale_factor);
+
+        // MSAA resolves into the first pass texture, which requires all of
+        // them (and the depth buffer) to have the same size.
+        if let Some(msaa) = self.msaa.as_ref() {
+            let samples = msaa.samples;
+
+            self.first_pass_texture = BindableTexture::new(
+                &self.context.device,
+                &self.context.texture_layout,
+                &TextureCreationParams {
+                    label: Some("First Pass Texture"),
+                    width: size.width,
+                    height: size.height,
+                    ..Default::default()
+                },
+            );
+
+            self.depth_texture = Arc::new(Texture::create_depth_texture(
+                &self.context.device,
+                &self.context.config.borrow(),
+                "Depth Texture",
+            ));
+
+            self.msaa = MsaaTargets::new(
+                &self.context.device,
+                size.width,
+                size.height,
+                samples,
+            );
+        }
+
+        self.viewport = apply_viewport(new_size);
+    }
+
+    /// Whether the scene is rendered into `first_pass_texture` rather than
+    /// straight to the surface.
+    pub fn renders_to_first_pass(&self) -> bool {
+        self.msaa.is_some() ||
+            self.viewport.is_some() ||
+            self.post_processing_effects.borrow().iter().any(|x| x.enabled)
+    }
+
+    /// Sample count of the passes drawing the scene, see `msaa_samples()`.
+    pub fn scene_sample_count(&self) -> u32 {
+        self.msaa.as_ref().map_or(1, |msaa| msaa.samples)
+    }
+
+    /// Returns `true` once the GPU device was lost, e.g. after a driver reset
+    /// or a GPU switch on laptops. The renderer can't be used anymore and has
+    /// to be created again, see `EngineState::recover_lost_device`.
+    pub fn is_device_lost(&self) -> bool {
+        self.context.device_lost.load(Ordering::SeqCst)
+    }
+
+    /// Gives back the window and its egui state, dropping everything else.
+    pub fn into_window_parts(self) -> (Window, Option<egui_winit::State>) {
+        (self.window, self.egui_winit)
+    }
+
+    /// Reconfigures the surface with the given present mode. Returns the mode
+    /// that was actually applied, which is `PresentMode::Fifo` if the
+    /// requested one isn't supported.
+    pub fn set_present_mode(&mut self, mode: PresentMode) -> PresentMode {
+        // One bit per `PresentMode`, so a settings menu that keeps asking for
+        // an unsupported mode doesn't repeat the warning.
+        static WARNED: AtomicU8 = AtomicU8::new(0);
+
+        let desired = match mode {
+            PresentMode::Fifo => wgpu::PresentMode::Fifo,
+            PresentMode::Immediate => wgpu::PresentMode::Immediate,
+            PresentMode::Mailbox => wgpu::PresentMode::Mailbox,
+        };
+
+        let caps = self.context.surface.get_capabilities(&self.context.adapter);
+
+        let (mode, present_mode) = if caps.present_modes.contains(&desired) {
+            (mode, desired)
+        } else {
+            let bit = 1 << mode as u8;
+
+            if WARNED.fetch_or(bit, Ordering::SeqCst) & bit == 0 {
+                warn!("Present mode {:?} isn't supported, using Fifo", mode);
+            }
+
+            (PresentMode::Fifo, wgpu::PresentMode::Fifo)
+        };
+
+        let mut config = self.context.config.borrow_mut();
+        config.present_mode = present_mode;
+        self.context.surface.configure(&self.context.device, &config);
+
+        mode
     }
 
     pub fn width(&self) -> f32 {
@@ -1037,3 +1428,16 @@ pub fn depth_stencil_attachment(
         None
     }
 }
+
+/// Computes the letterboxed viewport for a surface of `size` and stores it
+/// in `GLOBAL_STATE` along with the screen size, see `viewport_rect()`.
+fn apply_viewport(size: UVec2) -> Option<Rect> {
+    let (viewport, pixel_scale) = game_config().viewport(size);
+
+    let mut state = GLOBAL_STATE.borrow_mut();
+    state.screen_size = size.as_vec2();
+    state.viewport = viewport;
+    state.pixel_scale = pixel_scale;
+
+    viewport
+}
@@ -1,6 +1,204 @@
-#[cfg(feature = "record-pngs")]
 use crate::*;
 
+type MapResult = Arc<Mutex<Option<Result<(), wgpu::BufferAsyncError>>>>;
+
+/// A frame copied into a buffer that is waiting to be mapped for reading.
+pub struct PendingScreenshot {
+    buffer: wgpu::Buffer,
+    size: UVec2,
+    padded_bytes_per_row: u32,
+    swap_red_blue: bool,
+    requests: Vec<ScreenshotRequest>,
+    map_result: MapResult,
+}
+
+/// Copies `texture` into a readback buffer and starts mapping it. Returns
+/// `None` (after logging why) if the texture can't be captured.
+pub fn capture_screenshot(
+    context: &GraphicsContext,
+    texture: &wgpu::Texture,
+    requests: Vec<ScreenshotRequest>,
+) -> Option<PendingScreenshot> {
+    if !texture.usage().contains(wgpu::TextureUsages::COPY_SRC) {
+        error!("Screenshots aren't supported, the surface can't be copied");
+        return None;
+    }
+
+    let swap_red_blue = match texture.format() {
+        wgpu::TextureFormat::Bgra8Unorm |
+        wgpu::TextureFormat::Bgra8UnormSrgb => true,
+        wgpu::TextureFormat::Rgba8Unorm |
+        wgpu::TextureFormat::Rgba8UnormSrgb => false,
+        format => {
+            error!("Screenshots of {:?} surfaces aren't supported", format);
+            return None;
+        }
+    };
+
+    let size = uvec2(texture.width(), texture.height());
+    let padded_bytes_per_row = padded_bytes_per_row(size.x);
+
+    let buffer = context.device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Screenshot Buffer"),
+        size: (padded_bytes_per_row * size.y) as u64,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = context.device.create_command_encoder(
+        &wgpu::CommandEncoderDescriptor { label: Some("Screenshot Encoder") },
+    );
+
+    encoder.copy_texture_to_buffer(
+        wgpu::ImageCopyTexture {
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_bytes_per_row),
+                rows_per_image: Some(size.y),
+            },
+        },
+        texture.size(),
+    );
+
+    context.queue.submit(std::iter::once(encoder.finish()));
+
+    let map_result: MapResult = Arc::new(Mutex::new(None));
+
+    {
+        let map_result = map_result.clone();
+
+        buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+            *map_result.lock() = Some(result);
+        });
+    }
+
+    Some(PendingScreenshot {
+        buffer,
+        size,
+        padded_bytes_per_row,
+        swap_red_blue,
+        requests,
+        map_result,
+    })
+}
+
+/// Finishes screenshots whose buffers have been mapped, leaving the rest in
+/// `pending`. The pixels are handed off to `spawn` so that PNG encoding and
+/// user callbacks don't block the frame.
+pub fn finish_screenshots(
+    context: &GraphicsContext,
+    pending: &mut Vec<PendingScreenshot>,
+    spawn: impl Fn(Box<dyn FnOnce() + Send>),
+) {
+    if pending.is_empty() {
+        return;
+    }
+
+    context.device.poll(wgpu::Maintain::Poll);
+
+    let mut i = 0;
+
+    while i < pending.len() {
+        let result = pending[i].map_result.lock().take();
+
+        let Some(result) = result else {
+            i += 1;
+            continue;
+        };
+
+        let screenshot = pending.swap_remove(i);
+
+        if let Err(err) = result {
+            error!("Failed to read back screenshot: {}", err);
+            continue;
+        }
+
+        let pixels = {
+            let data = screenshot.buffer.slice(..).get_mapped_range();
+
+            unpad_rows(
+                &data,
+                screenshot.size,
+                screenshot.padded_bytes_per_row,
+                screenshot.swap_red_blue,
+            )
+        };
+
+        screenshot.buffer.unmap();
+
+        let size = screenshot.size;
+
+        for request in screenshot.requests {
+            let pixels = pixels.clone();
+
+            match request {
+                ScreenshotRequest::Png(path) => {
+                    spawn(Box::new(move || {
+                        match image::save_buffer(
+                            &path,
+                            &pixels,
+                            size.x,
+                            size.y,
+                            image::ColorType::Rgba8,
+                        ) {
+                            Ok(()) => info!("Saved screenshot to {:?}", path),
+                            Err(err) => {
+                                error!(
+                                    "Failed to save screenshot to {:?}: {}",
+                                    path, err
+                                );
+                            }
+                        }
+                    }));
+                }
+                ScreenshotRequest::Bytes(callback) => {
+                    spawn(Box::new(move || callback(size, pixels)));
+                }
+            }
+        }
+    }
+}
+
+/// Texture to buffer copies need rows aligned to
+/// `COPY_BYTES_PER_ROW_ALIGNMENT` (256 bytes).
+fn padded_bytes_per_row(width: u32) -> u32 {
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    let unpadded = width * 4;
+
+    unpadded.div_ceil(align) * align
+}
+
+/// Strips the row padding from a readback buffer and converts the pixels to
+/// RGBA8.
+fn unpad_rows(
+    data: &[u8],
+    size: UVec2,
+    padded_bytes_per_row: u32,
+    swap_red_blue: bool,
+) -> Vec<u8> {
+    let row_len = size.x as usize * 4;
+    let mut pixels = Vec::with_capacity(row_len * size.y as usize);
+
+    for row in data.chunks_exact(padded_bytes_per_row as usize) {
+        pixels.extend_from_slice(&row[..row_len]);
+    }
+
+    if swap_red_blue {
+        for pixel in pixels.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+    }
+
+    pixels
+}
+
 #[cfg(feature = "record-pngs")]
 pub fn record_pngs(
     screen: UVec2,
@@ -0,0 +1,178 @@
+use crate::*;
+
+/// An additional window next to the main game window, e.g. for debug tools.
+///
+/// Secondary windows only render egui using their own `egui::Context` (see
+/// `window_egui`), the game world is only drawn into the main window. They
+/// share the GPU device with the main renderer.
+pub struct SecondaryWindow {
+    // Declared before `window` so that the surface is dropped first.
+    surface: wgpu::Surface,
+    config: wgpu::SurfaceConfiguration,
+
+    pub egui_ctx: egui::Context,
+    pub egui_winit: egui_winit::State,
+    egui_render_routine: EguiRenderRoutine,
+
+    pub window: Window,
+}
+
+impl SecondaryWindow {
+    pub fn new(context: &GraphicsContext, window: Window) -> Result<Self> {
+        let surface = unsafe { context.instance.create_surface(&window)? };
+
+        let caps = surface.get_capabilities(&context.adapter);
+
+        let main_format = context.config.borrow().format;
+
+        let format = if caps.formats.contains(&main_format) {
+            main_format
+        } else {
+            match caps.formats.first() {
+                Some(format) => *format,
+                None => bail!("Surface of the new window isn't supported"),
+            }
+        };
+
+        let size = window.inner_size();
+
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format,
+            width: size.width.max(1),
+            height: size.height.max(1),
+            present_mode: wgpu::PresentMode::AutoVsync,
+            alpha_mode: caps.alpha_modes[0],
+            view_formats: vec![],
+        };
+
+        surface.configure(&context.device, &config);
+
+        let scale_factor = window.scale_factor() as f32;
+
+        let egui_ctx = egui::Context::default();
+
+        let egui_winit = egui_winit::State::new(
+            egui_ctx.viewport_id(),
+            &window,
+            Some(scale_factor),
+            None,
+        );
+
+        let egui_render_routine = EguiRenderRoutine::new(
+            &context.device,
+            format,
+            1,
+            config.width,
+            config.height,
+            scale_factor,
+        );
+
+        Ok(Self {
+            surface,
+            config,
+            egui_ctx,
+            egui_winit,
+            egui_render_routine,
+            window,
+        })
+    }
+
+    /// Drops the surface and gives back the window, e.g. to create the
+    /// surface again on a new device.
+    pub fn into_window(self) -> Window {
+        self.window
+    }
+
+    /// Returns `true` if egui consumed the event.
+    pub fn on_event(&mut self, event: &winit::event::WindowEvent) -> bool {
+        self.egui_winit.on_window_event(&self.egui_ctx, event).consumed
+    }
+
+    pub fn resize(&mut self, context: &GraphicsContext, new_size: UVec2) {
+        if new_size.x == 0 || new_size.y == 0 {
+            return;
+        }
+
+        self.config.width = new_size.x;
+        self.config.height = new_size.y;
+        self.surface.configure(&context.device, &self.config);
+
+        self.egui_render_routine.resize(
+            new_size.x,
+            new_size.y,
+            self.window.scale_factor() as f32,
+        );
+    }
+
+    pub fn begin_frame(&mut self) {
+        let input = self.egui_winit.take_egui_input(&self.window);
+        self.egui_ctx.begin_frame(input);
+    }
+
+    /// Ends the egui frame and presents it.
+    pub fn draw(&mut self, context: &GraphicsContext) {
+        let egui::FullOutput {
+            platform_output, shapes, textures_delta, ..
+        } = self.egui_ctx.end_frame();
+
+        self.egui_winit.handle_platform_output(
+            &self.window,
+            &self.egui_ctx,
+            platform_output,
+        );
+
+        let output = match self.surface.get_current_texture() {
+            Ok(texture) => texture,
+            Err(_) => return,
+        };
+
+        let view =
+            output.texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let paint_jobs =
+            self.egui_ctx.tessellate(shapes, self.window.scale_factor() as f32);
+
+        let routine = &mut self.egui_render_routine;
+        let mut encoder =
+            context.device.simple_encoder("Secondary Window Encoder");
+
+        for id in textures_delta.free {
+            routine.render_pass.free_texture(&id);
+        }
+
+        for (id, image_delta) in textures_delta.set {
+            routine.render_pass.update_texture(
+                &context.device,
+                &context.queue,
+                id,
+                &image_delta,
+            );
+        }
+
+        routine.render_pass.update_buffers(
+            &context.device,
+            &context.queue,
+            &mut encoder,
+            &paint_jobs,
+            &routine.screen_descriptor,
+        );
+
+        {
+            let mut render_pass = encoder.simple_render_pass(
+                "Secondary Window Render Pass",
+                Some(BLACK),
+                &view,
+            );
+
+            routine.render_pass.render(
+                &mut render_pass,
+                &paint_jobs,
+                &routine.screen_descriptor,
+            );
+        }
+
+        context.queue.submit(std::iter::once(encoder.finish()));
+        output.present();
+    }
+}
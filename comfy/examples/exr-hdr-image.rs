@@ -18,11 +18,13 @@ const TEST_IMAGES: &[&str] = &[
 ];
 
 fn setup(c: &mut EngineContext) {
+    let renderer = c.renderer();
+
     for path in TEST_IMAGES {
         let img =
             image::io::Reader::open(path).unwrap().decode().unwrap().flipv();
 
-        let gc = &c.renderer.context;
+        let gc = &renderer.context;
         let label = *path;
 
         let (img, texture) = if path.ends_with(".exr") {
@@ -68,11 +70,11 @@ fn setup(c: &mut EngineContext) {
         };
 
         load_texture_with_image(
-            &c.renderer.context,
+            &renderer.context,
             label,
             img,
             texture,
-            &mut c.renderer.textures.lock(),
+            &mut renderer.textures.lock(),
         );
     }
 }
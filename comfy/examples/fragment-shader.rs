@@ -28,7 +28,7 @@ fn update(state: &mut GameState, c: &mut EngineContext) {
             // Note that currently hot reloading an invalid shader will log the error in the
             // terminal, but will automatically fall back to the previous shader that compiled.
             create_reloadable_sprite_shader(
-                &mut c.renderer.shaders.borrow_mut(),
+                &mut c.renderer().shaders.borrow_mut(),
                 "my-shader",
                 ReloadableShaderSource {
                     static_source: include_str!("fragment-shader.wgsl")
@@ -34,7 +34,7 @@ fn update(state: &mut GameState, c: &mut EngineContext) {
     if state.my_shader_id.is_none() {
         state.my_shader_id = Some(
             create_shader(
-                &mut c.renderer.shaders.borrow_mut(),
+                &mut c.renderer().shaders.borrow_mut(),
                 "my-shader",
                 &sprite_shader_from_fragment(SHADER),
                 HashMap::new(),
@@ -43,7 +43,7 @@ fn update(state: &mut GameState, c: &mut EngineContext) {
         );
 
         state.my_render_target =
-            Some(create_render_target(c.renderer, &RenderTargetParams {
+            Some(create_render_target(c.renderer(), &RenderTargetParams {
                 label: "my-render-target".to_string(),
                 size: uvec2(128, 128),
                 filter_mode: wgpu::FilterMode::Nearest,
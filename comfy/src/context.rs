@@ -45,8 +45,12 @@ impl RbdSyncSettings {
 pub type ContextFn =
     Box<dyn FnOnce(&mut EngineContext) + Sync + Send + 'static>;
 
+/// Work queued with `EngineContext::defer_to_frame_end`.
+pub type DeferredFn = Box<dyn FnOnce(&mut EngineContext) + 'static>;
+
 pub struct EngineContext<'a> {
-    pub renderer: &'a mut WgpuRenderer,
+    /// `None` when running headless via `run_comfy_headless`.
+    pub renderer: Option<&'a mut WgpuRenderer>,
 
     pub delta: f32,
     pub frame: u64,
@@ -62,8 +66,31 @@ pub struct EngineContext<'a> {
     pub quit_flag: &'a mut bool,
     pub flags: &'a mut RefCell<HashSet<String>>,
 
+    /// Keyboard and mouse state of the current frame, a cheaper alternative
+    /// to the free `is_key_pressed` etc. functions for systems that query
+    /// input a lot.
+    pub input: &'a InputSnapshot,
+
+    /// Named action bindings, mutable so that a settings menu can rebind
+    /// them, see `is_action_pressed`.
+    pub input_map: &'a mut InputMap,
+
+    /// See `defer_to_frame_end`.
+    pub deferred: &'a mut Vec<DeferredFn>,
+
     // TODO: remove this, can be passed through GraphicsContext or WgpuRenderer
-    pub texture_creator: &'a Arc<AtomicRefCell<WgpuTextureCreator>>,
+    pub texture_creator: Option<&'a Arc<AtomicRefCell<WgpuTextureCreator>>>,
+
+    /// Shared pool for parallelizing game systems, sized by
+    /// `game_config().worker_threads`. Run parallel iterators inside
+    /// `thread_pool.install(|| ...)` so they use it instead of rayon's global
+    /// pool.
+    ///
+    /// Tasks must not touch the renderer, `GLOBAL_STATE`, the ECS world or
+    /// the other engine globals, they're only meant to be used from the main
+    /// thread. Compute results on the workers and apply them afterwards.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub thread_pool: &'a rayon::ThreadPool,
 }
 
 impl<'a> EngineContext<'a> {
@@ -74,12 +101,74 @@ impl<'a> EngineContext<'a> {
         blood_canvas_reset();
     }
 
+    /// Returns the renderer.
+    ///
+    /// Panics when called in headless mode, use the `renderer` field directly
+    /// if the game needs to support running without a window.
+    pub fn renderer(&mut self) -> &mut WgpuRenderer {
+        self.renderer
+            .as_deref_mut()
+            .expect("renderer is not available when running headless")
+    }
+
+    /// Returns the texture creator, see `renderer`.
+    ///
+    /// Panics when called in headless mode, use the `texture_creator` field
+    /// directly if the game needs to support running without a window.
+    pub fn texture_creator(&self) -> &Arc<AtomicRefCell<WgpuTextureCreator>> {
+        self.texture_creator
+            .expect("texture creator is not available when running headless")
+    }
+
+    /// Encoder for recording custom passes in `GameLoop::pre_render` and
+    /// `post_render`, see `WgpuRenderer::command_encoder`. Panics when
+    /// running headless.
+    pub fn command_encoder(&mut self) -> &mut wgpu::CommandEncoder {
+        self.renderer().command_encoder()
+    }
+
+    /// Runs `f` once at the end of this frame, after the late update stages
+    /// (which draw the frame). Useful for changes that can't be made while
+    /// iterating over something, e.g. spawning entities from inside a world
+    /// query. Closures deferred while the queue is running wait for the next
+    /// frame.
+    pub fn defer_to_frame_end(
+        &mut self,
+        f: impl FnOnce(&mut EngineContext) + 'static,
+    ) {
+        self.deferred.push(Box::new(f));
+    }
+
+    /// Returns `true` while any binding of the action is held.
+    pub fn is_action_pressed(&self, action: &str) -> bool {
+        self.input_map.is_pressed(action)
+    }
+
+    /// Returns `true` on the frame any binding of the action was pressed.
+    pub fn is_action_just_pressed(&self, action: &str) -> bool {
+        self.input_map.is_just_pressed(action)
+    }
+
+    /// Returns `true` on the frame any binding of the action was released.
+    pub fn is_action_just_released(&self, action: &str) -> bool {
+        self.input_map.is_just_released(action)
+    }
+
+    /// Value of the axis in `-1.0..=1.0`, `0.0` if there's no such axis.
+    pub fn action_axis(&self, axis: &str) -> f32 {
+        self.input_map.axis(axis)
+    }
+
+    /// Window size and scale factor for converting between physical and
+    /// logical pixels, see `Coords`.
+    pub fn coords(&self) -> Coords {
+        Coords::current()
+    }
+
     pub fn load_texture_from_bytes(&self, name: &str, bytes: &[u8]) {
-        load_texture_from_engine_bytes(
-            &self.renderer.context,
+        self.load_texture_from_bytes_ex(
             name,
             bytes,
-            &mut self.renderer.textures.lock(),
             wgpu::AddressMode::ClampToEdge,
         );
     }
@@ -90,13 +179,15 @@ impl<'a> EngineContext<'a> {
         bytes: &[u8],
         address_mode: wgpu::AddressMode,
     ) {
-        load_texture_from_engine_bytes(
-            &self.renderer.context,
-            name,
-            bytes,
-            &mut self.renderer.textures.lock(),
-            address_mode,
-        );
+        if let Some(renderer) = self.renderer.as_deref() {
+            load_texture_from_engine_bytes(
+                &renderer.context,
+                name,
+                bytes,
+                &mut renderer.textures.lock(),
+                address_mode,
+            );
+        }
     }
 
     pub fn load_fonts_from_bytes(&self, fonts: &[(&str, &[u8])]) {
@@ -1,12 +1,114 @@
-use comfy_wgpu::WgpuRenderer;
+use comfy_wgpu::{SecondaryWindow, WgpuRenderer};
 
 use crate::*;
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CloseResponse {
+    Exit,
+    Keep,
+}
+
 pub trait GameLoop: Sized {
     fn new(c: &mut EngineState) -> Self;
 
     fn performance_metrics(&self, _world: &mut World, _ui: &mut egui::Ui) {}
     fn update(&mut self, c: &mut EngineContext);
+
+    /// Called at a fixed rate of `game_config().fixed_timestep`, independent
+    /// of the framerate. Use `fixed_delta()` for the length of a tick and
+    /// `fixed_alpha()` to interpolate rendering between ticks.
+    fn fixed_update(&mut self, _c: &mut EngineContext) {}
+
+    /// Called every frame after `update`, even when `update` didn't run
+    /// because of `game_config().update_rate`. Meant for things tied to
+    /// rendering rather than the simulation, like moving the camera or
+    /// animations. Also runs while the engine is paused.
+    fn render_update(&mut self, _c: &mut EngineContext) {}
+
+    /// Called instead of `update` and `fixed_update` while the engine is
+    /// paused via `pause_engine()`. Rendering and egui keep running.
+    fn paused_update(&mut self, _c: &mut EngineContext) {}
+
+    /// Called once right before the game loop exits, either because the
+    /// window was closed, `quit()` was called or `quit_flag` was set, see
+    /// `quit_reason()` for which one. Use it to flush saves or close network
+    /// connections. Not called if the process is killed or exits through
+    /// `std::process::exit`, e.g. with the dev build F1+Escape shortcut.
+    fn on_exit(&mut self, _c: &mut EngineContext) {}
+
+    /// Called once after the renderer was created and before the first
+    /// frame, followed by the initial `on_resize`. From here on
+    /// `c.renderer` is set, so it's a good place for creating render targets,
+    /// shaders and uploading textures. Not called when running headless.
+    fn on_renderer_ready(&mut self, _c: &mut EngineContext) {}
+
+    /// Called after the window was resized, with the new size in physical
+    /// pixels, and once on startup with the initial size. Also see
+    /// `window_size()`.
+    fn on_resize(&mut self, _new_size: UVec2, _c: &mut EngineContext) {}
+
+    /// Called when the OS scale factor of the window changes, e.g. when it's
+    /// moved to a monitor with a different DPI. Runs after the resize that
+    /// comes with it, egui picks up the new factor on its own. Also see
+    /// `scale_factor()`.
+    fn on_scale_factor_changed(
+        &mut self,
+        _scale_factor: f32,
+        _c: &mut EngineContext,
+    ) {
+    }
+
+    /// Called when the user tries to close the window. Return
+    /// `CloseResponse::Keep` to stay open, e.g. to show a confirmation dialog,
+    /// and call `quit()` once the user confirms.
+    fn on_close_requested(&mut self, _c: &mut EngineContext) -> CloseResponse {
+        CloseResponse::Exit
+    }
+
+    /// Called for every event sent through `event_loop_proxy()`, right
+    /// before the frame it woke the loop up for.
+    fn on_user_event(
+        &mut self,
+        _event: ComfyUserEvent,
+        _c: &mut EngineContext,
+    ) {
+    }
+
+    /// Called once per frame for every window opened with `create_window`,
+    /// after the main window was drawn. Draw into the window through its own
+    /// egui context from `window_egui(id)`.
+    fn render_window(&mut self, _id: WindowId, _c: &mut EngineContext) {}
+
+    /// Called right before comfy draws the frame, e.g. to render into your
+    /// own textures with a custom pass. Work recorded into
+    /// `c.command_encoder()` here runs before comfy's passes.
+    fn pre_render(&mut self, _c: &mut EngineContext) {}
+
+    /// Called after comfy drew the world and post processing but before egui
+    /// is drawn on top and the frame is shown. Draw into the frame through
+    /// `c.renderer().frame_view()`, recording into `c.command_encoder()`.
+    fn post_render(&mut self, _c: &mut EngineContext) {}
+
+    /// Called after the renderer was created again because the GPU device
+    /// was lost, e.g. after a driver reset. Textures loaded through comfy are
+    /// uploaded again on their own, but shaders, render targets and anything
+    /// else created on the GPU by the game have to be created again here.
+    fn on_device_lost(&mut self, _c: &mut EngineContext) {}
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn create_worker_thread_pool() -> rayon::ThreadPool {
+    let threads = game_config().worker_threads.unwrap_or_else(|| {
+        std::thread::available_parallelism()
+            .map_or(1, |x| x.get().saturating_sub(1))
+            .max(1)
+    });
+
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .thread_name(|i| format!("comfy-worker-{}", i))
+        .build()
+        .expect("failed to create the worker thread pool")
 }
 
 pub type GameLoopBuilder = Box<dyn Fn() -> Arc<Mutex<dyn GameLoop>>>;
@@ -19,6 +121,11 @@ pub struct EngineState {
     pub fps_stats: MovingStats,
 
     pub renderer: Option<WgpuRenderer>,
+    /// Windows opened with `create_window`, in creation order.
+    pub windows: Vec<(WindowId, SecondaryWindow)>,
+    /// Windows requested with `create_window` that the event loop hasn't
+    /// created yet.
+    pub pending_windows: Vec<(WindowId, WindowConfig)>,
     pub texture_creator: Option<Arc<AtomicRefCell<WgpuTextureCreator>>>,
 
     pub meta: AnyMap,
@@ -28,6 +135,20 @@ pub struct EngineState {
     pub is_paused: RefCell<bool>,
     pub show_pause_menu: bool,
     pub quit_flag: bool,
+
+    /// See `capture_input`.
+    pub input: InputSnapshot,
+    /// Action bindings, starts out as `game_config().input_map`.
+    pub input_map: InputMap,
+
+    /// Queued by `EngineContext::defer_to_frame_end`.
+    pub deferred: Vec<DeferredFn>,
+
+    /// Set up by `start`, see `tick`.
+    pub(crate) frame_loop: Option<FrameLoop>,
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub thread_pool: rayon::ThreadPool,
 }
 
 impl EngineState {
@@ -47,8 +168,14 @@ impl EngineState {
             }
         }
 
-        srand(thread_rng().next_u64());
+        srand(
+            game_config().rng_seed.unwrap_or_else(|| thread_rng().next_u64()),
+        );
         set_main_camera_zoom(30.0);
+        match game_config().target_framerate {
+            GameConfig::AUTO_FRAMERATE => set_target_framerate_auto(),
+            framerate => set_target_framerate(framerate as f32),
+        }
 
         ASSETS.borrow_mut().load_sound_from_bytes(
             "error",
@@ -61,6 +188,8 @@ impl EngineState {
 
         Self {
             renderer: None,
+            windows: Vec::new(),
+            pending_windows: Vec::new(),
             texture_creator: None,
 
             dt_stats: MovingStats::new(20),
@@ -76,6 +205,14 @@ impl EngineState {
             is_paused: RefCell::new(false),
             show_pause_menu: false,
             quit_flag: false,
+
+            input: InputSnapshot::default(),
+            input_map: game_config().input_map.clone(),
+            deferred: Vec::new(),
+            frame_loop: None,
+
+            #[cfg(not(target_arch = "wasm32"))]
+            thread_pool: create_worker_thread_pool(),
         }
     }
 
@@ -93,11 +230,8 @@ impl EngineState {
     // }
 
     pub fn make_context(&mut self) -> EngineContext {
-        let renderer = self.renderer.as_mut().unwrap();
-        let texture_creator = self.texture_creator.as_ref().unwrap();
-
         EngineContext {
-            renderer,
+            renderer: self.renderer.as_mut(),
 
             delta: delta(),
 
@@ -116,7 +250,15 @@ impl EngineState {
             show_pause_menu: &mut self.show_pause_menu,
             quit_flag: &mut self.quit_flag,
 
-            texture_creator,
+            input: &self.input,
+            input_map: &mut self.input_map,
+
+            deferred: &mut self.deferred,
+
+            texture_creator: self.texture_creator.as_ref(),
+
+            #[cfg(not(target_arch = "wasm32"))]
+            thread_pool: &self.thread_pool,
         }
     }
 
@@ -125,10 +267,308 @@ impl EngineState {
     //     .expect("client must be running")
     //     .secondary_frame_mark(tracy_client::frame_name!("update"));
 
+    /// Runs the closures queued with `EngineContext::defer_to_frame_end`.
+    /// Anything they defer in turn runs on the next call.
+    pub fn run_deferred(&mut self) {
+        let deferred = std::mem::take(&mut self.deferred);
+
+        if deferred.is_empty() {
+            return;
+        }
+
+        let mut c = self.make_context();
+
+        for f in deferred {
+            f(&mut c);
+        }
+    }
+
+    /// Copies this frame's input into `input`. Called once per frame after
+    /// input recording/playback, so the snapshot matches what the free input
+    /// functions return during the update.
+    pub fn capture_input(&mut self) {
+        self.input.capture(&GLOBAL_STATE.borrow());
+    }
+
     pub fn resize(&mut self, new_size: UVec2) {
-        self.renderer
-            .as_mut()
-            .expect("renderer must be initialized")
-            .resize(new_size);
+        if let Some(renderer) = self.renderer.as_mut() {
+            renderer.resize(new_size);
+        }
+
+        GLOBAL_STATE.borrow_mut().window_size = new_size;
+    }
+
+    /// Applies everything queued with `queue_command` since the last frame.
+    /// Commands that need the window are dropped when running headless.
+    pub fn apply_commands(&mut self) {
+        for command in take_engine_commands() {
+            if let EngineCommand::Quit = command {
+                quit();
+                continue;
+            }
+
+            let Some(renderer) = self.renderer.as_mut() else {
+                continue;
+            };
+
+            match command {
+                EngineCommand::SetTitle(title) => {
+                    renderer.window.set_title(&title);
+                }
+                EngineCommand::SetWindowIcon(icon) => {
+                    renderer.window.set_window_icon(icon.to_winit());
+                }
+                EngineCommand::SetWindowVisible(visible) => {
+                    renderer.window.set_visible(visible);
+                }
+                EngineCommand::SetDecorations(decorations) => {
+                    renderer.window.set_decorations(decorations);
+                }
+                EngineCommand::SetResizable(resizable) => {
+                    renderer.window.set_resizable(resizable);
+                }
+                EngineCommand::StartWindowDrag => {
+                    if let Err(err) = renderer.window.drag_window() {
+                        warn!("Failed to start dragging the window: {}", err);
+                    }
+                }
+                EngineCommand::SetCursorVisible(visible) => {
+                    renderer.window.set_cursor_visible(visible);
+                    GLOBAL_STATE.borrow_mut().cursor_hidden = !visible;
+                }
+                EngineCommand::SetCursor(cursor) => {
+                    apply_cursor(&renderer.window, &cursor);
+                    GLOBAL_STATE.borrow_mut().cursor = cursor;
+                }
+                EngineCommand::SetCursorGrab(grab) => {
+                    let mode = grab_cursor(&renderer.window, grab);
+                    GLOBAL_STATE.borrow_mut().cursor_grab_mode = mode;
+                }
+                EngineCommand::ConfineCursor(rect) => {
+                    set_cursor_confine_rect(&renderer.window, rect);
+                }
+                EngineCommand::SetFullscreen(mode) => {
+                    let fullscreen =
+                        to_winit_fullscreen(&renderer.window, mode);
+                    renderer.window.set_fullscreen(fullscreen);
+                }
+                EngineCommand::SetPresentMode(mode) => {
+                    let mode = renderer.set_present_mode(mode);
+                    GLOBAL_STATE.borrow_mut().present_mode = Some(mode);
+                }
+                EngineCommand::Screenshot(request) => {
+                    renderer.screenshot_requests.push(request);
+                }
+                EngineCommand::CreateWindow(id, config) => {
+                    self.pending_windows.push((id, config));
+                }
+                EngineCommand::CloseWindow(id) => self.close_window(id),
+                EngineCommand::WithWindow(f) => f(&renderer.window),
+                EngineCommand::Quit => {}
+            }
+        }
+    }
+
+    /// Creates the windows queued by `create_window`. Needs the event loop,
+    /// so it's called from within it.
+    pub fn create_pending_windows(
+        &mut self,
+        target: &winit::event_loop::EventLoopWindowTarget<ComfyUserEvent>,
+    ) {
+        let Some(renderer) = self.renderer.as_ref() else {
+            return;
+        };
+
+        for (id, config) in self.pending_windows.drain(..) {
+            let window = winit::window::WindowBuilder::new()
+                .with_title(config.title)
+                .with_inner_size(winit::dpi::LogicalSize::new(
+                    config.size.x,
+                    config.size.y,
+                ))
+                .with_resizable(config.resizable)
+                .build(target);
+
+            let window = match window {
+                Ok(window) => window,
+                Err(err) => {
+                    error!("Failed to create window {:?}: {}", id, err);
+                    continue;
+                }
+            };
+
+            match SecondaryWindow::new(&renderer.context, window) {
+                Ok(window) => {
+                    set_window_egui(id, Some(window.egui_ctx.clone()));
+                    self.windows.push((id, window));
+                }
+                Err(err) => {
+                    error!("Failed to create window {:?}: {}", id, err);
+                }
+            }
+        }
+    }
+
+    /// Replaces the renderer with a new one on a new device once the old
+    /// device was lost, see `WgpuRenderer::is_device_lost`. Loaded textures
+    /// and the egui font atlas are uploaded again and secondary windows get
+    /// new surfaces. Textures the game created through egui itself are not
+    /// kept and have to be loaded again in `GameLoop::on_device_lost`.
+    ///
+    /// Quits if no new device can be created.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn recover_lost_device(&mut self) {
+        let Some(renderer) = self.renderer.take() else {
+            return;
+        };
+
+        warn!("Recreating the renderer after the GPU device was lost");
+
+        let windows = std::mem::take(&mut self.windows)
+            .into_iter()
+            .map(|(id, window)| (id, window.into_window()))
+            .collect::<Vec<_>>();
+
+        let (window, egui_winit) = renderer.into_window_parts();
+        let renderer =
+            match pollster::block_on(WgpuRenderer::try_new(window, egui_winit))
+            {
+                Ok(renderer) => renderer,
+                Err(err) => {
+                    error!("Failed to recreate the renderer: {}", err);
+                    quit();
+                    return;
+                }
+            };
+
+        {
+            let assets = ASSETS.borrow();
+
+            let paths = assets
+                .textures
+                .iter()
+                .map(|(path, handle)| (*handle, path.as_str()))
+                .collect::<HashMap<_, _>>();
+
+            for (handle, image) in assets.texture_image_map.lock().iter() {
+                let image = LoadedImage {
+                    path: paths.get(handle).map_or_else(
+                        || format!("{:?}", handle),
+                        |path| path.to_string(),
+                    ),
+                    handle: *handle,
+                    image: image.clone(),
+                };
+
+                renderer.loaded_image_send.send(image).log_err();
+            }
+        }
+
+        // egui only sends the font atlas in full once, later frames just have
+        // the glyphs that were added since.
+        let tex_manager = egui().tex_manager();
+        let font_texture = egui::TextureId::default();
+
+        // The atlas only exists after the first egui frame.
+        if tex_manager.read().meta(font_texture).is_some() {
+            let font_image = egui().fonts(|fonts| fonts.image());
+
+            tex_manager.write().set(
+                font_texture,
+                egui::epaint::ImageDelta::full(
+                    font_image,
+                    egui::epaint::textures::TextureOptions::LINEAR,
+                ),
+            );
+        }
+
+        for (id, window) in windows {
+            match SecondaryWindow::new(&renderer.context, window) {
+                Ok(window) => {
+                    set_window_egui(id, Some(window.egui_ctx.clone()));
+                    self.windows.push((id, window));
+                }
+                Err(err) => {
+                    error!("Failed to recreate window {:?}: {}", id, err);
+                    set_window_egui(id, None);
+                }
+            }
+        }
+
+        self.texture_creator = Some(renderer.texture_creator.clone());
+        self.renderer = Some(renderer);
+    }
+
+    pub fn close_window(&mut self, id: WindowId) {
+        self.windows.retain(|(window_id, _)| *window_id != id);
+        set_window_egui(id, None);
+    }
+
+    /// Handles an event of a secondary window. Returns `false` if the event
+    /// belongs to the main window.
+    pub fn handle_secondary_window_event(
+        &mut self,
+        window_id: winit::window::WindowId,
+        event: &WindowEvent,
+    ) -> bool {
+        let Some(index) = self
+            .windows
+            .iter()
+            .position(|(_, window)| window.window.id() == window_id)
+        else {
+            return false;
+        };
+
+        let (id, window) = &mut self.windows[index];
+
+        match event {
+            WindowEvent::CloseRequested => {
+                let id = *id;
+                self.close_window(id);
+                return true;
+            }
+            WindowEvent::Resized(size) => {
+                if let Some(renderer) = self.renderer.as_ref() {
+                    window.resize(
+                        &renderer.context,
+                        uvec2(size.width, size.height),
+                    );
+                }
+            }
+            WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
+                if let Some(renderer) = self.renderer.as_ref() {
+                    window.resize(
+                        &renderer.context,
+                        uvec2(new_inner_size.width, new_inner_size.height),
+                    );
+                }
+            }
+            _ => {}
+        }
+
+        // Input of secondary windows only goes to their egui context, the
+        // global input state always follows the main window.
+        window.on_event(event);
+
+        true
+    }
+
+    /// Runs `GameLoop::render_window` for each secondary window and presents
+    /// them.
+    pub fn render_secondary_windows(&mut self, game: &mut impl GameLoop) {
+        let mut windows = std::mem::take(&mut self.windows);
+
+        for (id, window) in windows.iter_mut() {
+            window.begin_frame();
+
+            let mut c = self.make_context();
+            game.render_window(*id, &mut c);
+
+            if let Some(renderer) = self.renderer.as_ref() {
+                window.draw(&renderer.context);
+            }
+        }
+
+        self.windows = windows;
     }
 }
@@ -3,17 +3,25 @@ use winit::event_loop::ControlFlow;
 use crate::*;
 
 pub async fn run_comfy_main_async(
-    mut game: impl GameLoop + 'static,
-    mut engine: EngineState,
+    game: impl GameLoop + 'static,
+    engine: EngineState,
 ) {
-    let _tracy = maybe_setup_tracy();
-
-    #[cfg(not(target_arch = "wasm32"))]
-    let target_framerate = game_config().target_framerate;
+    if let Err(err) = try_run_comfy_main_async(game, engine).await {
+        panic!("Failed to start the game: {}", err);
+    }
+}
 
-    #[cfg(not(target_arch = "wasm32"))]
-    let mut loop_helper = spin_sleep::LoopHelper::builder()
-        .build_with_target_rate(target_framerate);
+/// Like `run_comfy_main_async`, but returns an error when the window or the
+/// GPU can't be set up, e.g. to show a "no compatible GPU" message instead of
+/// crashing.
+///
+/// Only returns on errors: once the event loop runs it takes over the thread
+/// and the process exits when the game does.
+pub async fn try_run_comfy_main_async(
+    game: impl GameLoop + 'static,
+    engine: EngineState,
+) -> Result<(), ComfyInitError> {
+    let _tracy = maybe_setup_tracy();
 
     let resolution = {
         use std::env::var;
@@ -29,27 +37,15 @@ pub async fn run_comfy_main_async(
         }
     };
 
-    let event_loop = winit::event_loop::EventLoop::new();
+    let event_loop =
+        winit::event_loop::EventLoopBuilder::with_user_event().build();
 
-    let title = {
-        let game_name = game_config().game_name.clone();
-        let dev_name = format!("{} (Comfy Engine DEV BUILD)", game_name);
+    let title = window_title_with_dev_suffix(&game_config().game_name);
 
-        match std::env::var("COMFY_DEV_TITLE") {
-            Ok(_) => dev_name,
-            Err(_) => {
-                cfg_if! {
-                    if #[cfg(feature = "dev")] {
-                        dev_name
-                    } else {
-                        game_name
-                    }
-                }
-            }
-        }
-    };
-
-    let window = winit::window::WindowBuilder::new().with_title(title);
+    let window =
+        winit::window::WindowBuilder::new().with_title(title).with_window_icon(
+            game_config().window_icon.as_ref().and_then(WindowIcon::to_winit),
+        );
 
     let window = match resolution {
         ResolutionConfig::Physical(w, h) => {
@@ -59,25 +55,42 @@ pub async fn run_comfy_main_async(
         ResolutionConfig::Logical(w, h) => {
             window.with_inner_size(winit::dpi::LogicalSize::new(w, h))
         }
-    };
 
-    let window = window.build(&event_loop).unwrap();
+        ResolutionConfig::Aspect { design } => {
+            let monitor_size = event_loop.primary_monitor().map(|monitor| {
+                uvec2(monitor.size().width, monitor.size().height)
+            });
 
-    let min_resolution = match game_config_mut()
-        .min_resolution
-        .ensure_non_zero()
-    {
-        ResolutionConfig::Physical(w, h) => {
+            let size = initial_aspect_window_size(design, monitor_size);
             window
-                .set_min_inner_size(Some(winit::dpi::PhysicalSize::new(w, h)));
-            (w, h)
-        }
-        ResolutionConfig::Logical(w, h) => {
-            window.set_min_inner_size(Some(winit::dpi::LogicalSize::new(w, h)));
-            (w, h)
+                .with_inner_size(winit::dpi::PhysicalSize::new(size.x, size.y))
         }
     };
 
+    let window_state = game_config().window_state;
+
+    let window = window
+        .with_decorations(game_config().decorations)
+        .with_maximized(window_state == WindowStartState::Maximized)
+        .with_visible(matches!(
+            window_state,
+            WindowStartState::Normal | WindowStartState::Maximized
+        ));
+
+    let window = match window_position_from_env(&event_loop) {
+        Some(position) => window.with_position(position),
+        None => window,
+    };
+
+    #[cfg(target_arch = "wasm32")]
+    let window = {
+        use winit::platform::web::WindowBuilderExtWebSys;
+        window.with_prevent_default(game_config().wasm_prevent_default)
+    };
+
+    let window =
+        window.build(&event_loop).map_err(ComfyInitError::WindowCreation)?;
+
     #[cfg(target_arch = "wasm32")]
     {
         // Winit prevents sizing with CSS, so we have to set
@@ -110,202 +123,1348 @@ pub async fn run_comfy_main_async(
             .expect("Couldn't append canvas to document body.");
     }
 
+    run_in_window(
+        game,
+        engine,
+        event_loop,
+        window,
+        window_state == WindowStartState::HiddenUntilFirstFrame,
+    )
+    .await
+}
+
+/// Runs the game in a window created by the caller instead of comfy, e.g.
+/// when embedding comfy into a larger winit app or for window options comfy
+/// doesn't expose. The event loop has to be created with
+/// `EventLoopBuilder::<ComfyUserEvent>::with_user_event()`.
+///
+/// The window is adopted as is, so `GameConfig::resolution`, `window_state`,
+/// `decorations`, the window icon and the `COMFY_RES_*`/`COMFY_WINDOW_*`
+/// environment variables have no effect. On wasm the canvas also has to be
+/// added to the page by the caller. Like `try_run_comfy_main_async` this only
+/// returns on errors.
+pub async fn run_comfy_main_async_with_window(
+    game: impl GameLoop + 'static,
+    engine: EngineState,
+    event_loop: winit::event_loop::EventLoop<ComfyUserEvent>,
+    window: Window,
+) -> Result<(), ComfyInitError> {
+    let _tracy = maybe_setup_tracy();
+
+    let size = window.inner_size();
+
+    if size.width == 0 || size.height == 0 {
+        return Err(ComfyInitError::ZeroSizedWindow);
+    }
+
+    run_in_window(game, engine, event_loop, window, false).await
+}
+
+/// Sets up egui and the renderer for `window` and runs the event loop.
+async fn run_in_window(
+    mut game: impl GameLoop + 'static,
+    mut engine: EngineState,
+    event_loop: winit::event_loop::EventLoop<ComfyUserEvent>,
+    window: Window,
+    show_window_after_present: bool,
+) -> Result<(), ComfyInitError> {
+    set_event_loop_proxy(event_loop.create_proxy());
+
     info!("scale factor = {}", window.scale_factor());
 
-    let egui_winit = egui_winit::State::new(
-        egui().viewport_id(),
-        &window,
-        Some(window.scale_factor() as f32),
-        None,
-    );
+    let egui_winit = game_config().enable_egui.then(|| {
+        egui_winit::State::new(
+            egui().viewport_id(),
+            &window,
+            Some(window.scale_factor() as f32),
+            None,
+        )
+    });
 
-    let mut delta = 1.0 / 60.0;
+    if let Some(style) = game_config().egui_style.clone() {
+        set_egui_style(style);
+    }
 
-    let renderer = WgpuRenderer::new(window, egui_winit).await;
+    let mut renderer = WgpuRenderer::try_new(window, egui_winit).await?;
+    renderer.show_window_after_present = show_window_after_present;
 
     engine.texture_creator = Some(renderer.texture_creator.clone());
     engine.renderer = Some(renderer);
 
-    event_loop.run(move |event, _, control_flow| {
+    engine.start(&mut game);
+
+    event_loop.run(move |event, target, control_flow| {
         match event {
-            Event::MainEventsCleared => {
-                let _span = span!("frame with vsync");
-                #[cfg(not(target_arch = "wasm32"))]
-                let _ = loop_helper.loop_start();
-                let frame_start = Instant::now();
-
-                set_delta(delta);
-                set_time(get_time() + delta as f64);
-                use_default_shader();
-
-                if engine.quit_flag {
-                    *control_flow = ControlFlow::Exit;
-                }
+            Event::MainEventsCleared | Event::RedrawRequested(_) => {
+                // Redraws only run a frame for the resize that requested
+                // them, see `GameConfig::render_during_resize`. Normally the
+                // resize was already applied by the frame in
+                // `MainEventsCleared`, which comes first.
+                if let Event::RedrawRequested(window_id) = event {
+                    let is_main_window = engine
+                        .renderer
+                        .as_ref()
+                        .is_some_and(|x| x.window.id() == window_id);
 
-                {
-                    span_with_timing!("frame");
-                    {
-                        let _span = span!("begin_frame");
-                        let renderer = engine.renderer.as_mut().unwrap();
-
-                        egui().begin_frame(
-                            renderer
-                                .egui_winit
-                                .take_egui_input(&renderer.window),
-                        );
+                    let resize_pending = engine
+                        .frame_loop
+                        .as_ref()
+                        .is_some_and(|x| x.pending_resize.is_some());
+
+                    if !(is_main_window && resize_pending) {
+                        return;
                     }
+                }
 
-                    engine.frame += 1;
+                *control_flow = engine.tick(&mut game);
+                engine.create_pending_windows(target);
+            }
+
+            Event::WindowEvent { ref event, window_id } => {
+                engine.handle_window_event(&mut game, window_id, event);
+            }
 
-                    // All internal engine code expect an `EngineContext`.
-                    let mut c = engine.make_context();
-                    run_early_update_stages(&mut c);
-                    game.update(&mut c);
-                    update_perf_counters(&mut c, &game);
-                    run_late_update_stages(&mut c, delta);
+            Event::DeviceEvent { ref event, .. } => {
+                engine.handle_device_event(event);
+            }
+
+            Event::UserEvent(event) => {
+                engine.handle_user_event(&mut game, event);
+            }
+
+            Event::LoopDestroyed => {
+                engine.shutdown(&mut game);
+            }
+
+            _ => {}
+        }
+    });
+}
+
+/// Frame loop state that lives across frames, like the frame pacing and the
+/// delta carried over to the next frame. Created by `EngineState::start`.
+pub(crate) struct FrameLoop {
+    #[cfg(not(target_arch = "wasm32"))]
+    pacing: PacingConfig,
+    #[cfg(not(target_arch = "wasm32"))]
+    loop_helper: LoopHelper,
+    // Picks up `set_target_framerate` changes.
+    #[cfg(not(target_arch = "wasm32"))]
+    applied_framerate: f32,
+    #[cfg(not(target_arch = "wasm32"))]
+    unfocused_loop_helper: Option<LoopHelper>,
+
+    delta: f64,
+    // Time cut off by `max_delta` that's still owed to the game, see
+    // `GameConfig::accumulate_dropped_time`.
+    carried_delta: f64,
+    fixed_accumulator: f32,
+    update_accumulator: f32,
+
+    gamepads: GamepadPoller,
+    hitch_logger: HitchLogger,
+    gamepad_ui_nav: GamepadUiNav,
+
+    benchmark: Option<BenchmarkConfig>,
+    min_resolution: (u32, u32),
+
+    // Whether anything happened since the last frame, see
+    // `LoopMode::Reactive`.
+    had_events: bool,
+    last_frame: Instant,
+    // Dragging the window edge sends a flood of resize events, only the
+    // latest size is applied at the start of the next frame so the surface
+    // is reconfigured once.
+    pub(crate) pending_resize: Option<UVec2>,
+    pending_scale_factor: Option<f32>,
+    // The window might be on another monitor, see
+    // `set_target_framerate_auto`.
+    monitor_changed: bool,
+    was_framerate_auto: bool,
+    // Start of the previous frame in `performance_now()` milliseconds.
+    #[cfg(target_arch = "wasm32")]
+    last_frame_start_ms: Option<f64>,
+    // When the next frame is due under `set_target_framerate`, see
+    // `skip_browser_frame`.
+    #[cfg(target_arch = "wasm32")]
+    next_frame_due_ms: Option<f64>,
+    // Text input comes from `Ime::Commit` while an IME is composing, some
+    // platforms also send the committed text as `ReceivedCharacter`s.
+    ime_composing: bool,
+    ime_committed: VecDeque<char>,
+
+    exit_handled: bool,
+}
+
+impl EngineState {
+    /// Prepares the engine for the first `tick`. Expects `renderer` to be
+    /// set, and is called by `run_comfy_main_async` right before it starts
+    /// the event loop.
+    ///
+    /// When driving comfy from your own event loop, call this once after
+    /// creating the renderer, then forward the window events to
+    /// `handle_window_event` and call `tick` once per frame.
+    pub fn start(&mut self, game: &mut impl GameLoop) {
+        let window = &self.renderer.as_ref().unwrap().window;
+
+        let cursor = game_config().cursor_icon.clone();
+        apply_cursor(window, &cursor);
+        GLOBAL_STATE.borrow_mut().cursor = cursor;
+
+        let min_resolution =
+            match game_config_mut().min_resolution.ensure_non_zero() {
+                ResolutionConfig::Physical(w, h) => {
+                    window.set_min_inner_size(Some(
+                        winit::dpi::PhysicalSize::new(w, h),
+                    ));
+                    (w, h)
+                }
+                ResolutionConfig::Logical(w, h) |
+                ResolutionConfig::Aspect { design: (w, h) } => {
+                    window.set_min_inner_size(Some(
+                        winit::dpi::LogicalSize::new(w, h),
+                    ));
+                    (w, h)
                 }
+            };
 
-                {
-                    let mut global_state = GLOBAL_STATE.borrow_mut();
-                    global_state.just_pressed.clear();
-                    global_state.just_released.clear();
-                    global_state.mouse_just_pressed.clear();
-                    global_state.mouse_just_released.clear();
-                    global_state.mouse_wheel = (0.0, 0.0);
+        match game_config().max_resolution {
+            Some(ResolutionConfig::Physical(w, h)) => {
+                window.set_max_inner_size(Some(winit::dpi::PhysicalSize::new(
+                    w.max(min_resolution.0),
+                    h.max(min_resolution.1),
+                )));
+            }
+            Some(
+                ResolutionConfig::Logical(w, h) |
+                ResolutionConfig::Aspect { design: (w, h) },
+            ) => {
+                window.set_max_inner_size(Some(winit::dpi::LogicalSize::new(
+                    w.max(min_resolution.0),
+                    h.max(min_resolution.1),
+                )));
+            }
+            None => {}
+        }
+
+        let initial_size = {
+            let mut global_state = GLOBAL_STATE.borrow_mut();
+            global_state.window_focused = true;
+            global_state.window_scale_factor = window.scale_factor() as f32;
+            global_state.window_size =
+                uvec2(window.inner_size().width, window.inner_size().height);
+
+            global_state.window_size
+        };
+
+        update_window_geometry(window);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let pacing = game_config().pacing;
+
+        let (min_delta, max_delta) = delta_clamp_range();
+
+        self.frame_loop = Some(FrameLoop {
+            #[cfg(not(target_arch = "wasm32"))]
+            pacing,
+            #[cfg(not(target_arch = "wasm32"))]
+            loop_helper: pacing.loop_helper(target_framerate()),
+            #[cfg(not(target_arch = "wasm32"))]
+            applied_framerate: target_framerate(),
+            #[cfg(not(target_arch = "wasm32"))]
+            unfocused_loop_helper: game_config()
+                .unfocused_framerate
+                .map(|framerate| pacing.loop_helper(framerate as f32)),
+
+            delta: game_config().fixed_frame_delta.unwrap_or(
+                game_config().initial_delta.clamp(min_delta, max_delta),
+            ) as f64,
+            carried_delta: 0.0,
+            fixed_accumulator: 0.0,
+            update_accumulator: 0.0,
+
+            gamepads: GamepadPoller::new(),
+            hitch_logger: HitchLogger::new(),
+            gamepad_ui_nav: GamepadUiNav::new(),
+
+            benchmark: game_config().benchmark,
+            min_resolution,
+
+            had_events: true,
+            last_frame: Instant::now(),
+            pending_resize: None,
+            pending_scale_factor: None,
+            monitor_changed: true,
+            was_framerate_auto: false,
+            #[cfg(target_arch = "wasm32")]
+            last_frame_start_ms: None,
+            #[cfg(target_arch = "wasm32")]
+            next_frame_due_ms: None,
+            ime_composing: false,
+            ime_committed: VecDeque::new(),
+
+            exit_handled: false,
+        });
+
+        start_real_time();
+
+        match game_config().benchmark {
+            Some(benchmark) => {
+                // Keep every frame of the run for the final report.
+                frame_stats_mut().set_capacity(benchmark.frames as usize);
+                set_present_mode(PresentMode::Immediate);
+            }
+            None => {
+                frame_stats_mut()
+                    .set_capacity(game_config().frame_stats_window);
+            }
+        }
+
+        game.on_renderer_ready(&mut self.make_context());
+
+        update_safe_area_insets();
+        game.on_resize(initial_size, &mut self.make_context());
+
+        // Wakes up the reactive loop mode for egui animations and tooltips.
+        egui().set_request_repaint_callback(|info| {
+            if let Some(time) = Instant::now().checked_add(info.delay) {
+                schedule_redraw(time);
+            }
+        });
+    }
+
+    /// Runs a single frame, including the frame limiter sleep. Returns how
+    /// the event loop should wait for the next one, `ControlFlow::Exit` once
+    /// the game is quitting.
+    ///
+    /// Windows requested with `create_window` are created by
+    /// `create_pending_windows`, which has to be called separately as it
+    /// needs the event loop.
+    pub fn tick(&mut self, game: &mut impl GameLoop) -> ControlFlow {
+        let mut frame_loop = self.take_frame_loop();
+        let control_flow = frame_loop.tick(self, game);
+        self.frame_loop = Some(frame_loop);
+
+        control_flow
+    }
+
+    /// Feeds a window event into the input state, egui and the game. Events
+    /// of windows that don't belong to comfy are ignored.
+    pub fn handle_window_event(
+        &mut self,
+        game: &mut impl GameLoop,
+        window_id: winit::window::WindowId,
+        event: &WindowEvent,
+    ) {
+        let mut frame_loop = self.take_frame_loop();
+        frame_loop.handle_window_event(self, game, window_id, event);
+        self.frame_loop = Some(frame_loop);
+    }
+
+    pub fn handle_device_event(&mut self, event: &DeviceEvent) {
+        if let DeviceEvent::MouseMotion { delta: (dx, dy) } = event {
+            if let Some(frame_loop) = self.frame_loop.as_mut() {
+                frame_loop.had_events = true;
+            }
+
+            GLOBAL_STATE.borrow_mut().mouse_delta +=
+                vec2(*dx as f32, *dy as f32);
+        }
+    }
+
+    /// Passes an event sent through `event_loop_proxy()` to
+    /// `GameLoop::on_user_event` and makes sure the next `tick` runs a frame.
+    pub fn handle_user_event(
+        &mut self,
+        game: &mut impl GameLoop,
+        event: ComfyUserEvent,
+    ) {
+        // The next `tick` already looks at the new redraw time.
+        if matches!(event, ComfyUserEvent::Reschedule) {
+            return;
+        }
+
+        if let Some(frame_loop) = self.frame_loop.as_mut() {
+            frame_loop.had_events = true;
+        }
+
+        let mut c = self.make_context();
+        game.on_user_event(event, &mut c);
+    }
+
+    /// Runs `GameLoop::on_exit`. Only the first call does anything, both
+    /// closing the window and `quit_flag` end up here.
+    pub fn shutdown(&mut self, game: &mut impl GameLoop) {
+        let Some(frame_loop) = self.frame_loop.as_mut() else {
+            return;
+        };
+
+        if frame_loop.exit_handled {
+            return;
+        }
+
+        frame_loop.exit_handled = true;
+
+        let mut c = self.make_context();
+        game.on_exit(&mut c);
+    }
+
+    fn take_frame_loop(&mut self) -> FrameLoop {
+        self.frame_loop
+            .take()
+            .expect("EngineState::start must be called before the first tick")
+    }
+}
+
+impl FrameLoop {
+    /// The browser decides when frames run and the loop can't sleep, so the
+    /// framerate limit is applied by skipping browser frames instead. At
+    /// 60Hz a limit of 30 runs every second frame, 20 every third and so on.
+    #[cfg(target_arch = "wasm32")]
+    fn skip_browser_frame(&mut self) -> bool {
+        // Browser frames arrive with some jitter, one that's a bit early
+        // still counts as on time.
+        const SLACK_MS: f64 = 2.0;
+
+        let fps = target_framerate();
+
+        // The browser already runs frames at the refresh rate.
+        if fps <= 0.0 || is_target_framerate_auto() {
+            self.next_frame_due_ms = None;
+            return false;
+        }
+
+        let now = performance_now();
+        let period = 1000.0 / fps as f64;
+
+        match self.next_frame_due_ms {
+            Some(due) if now < due - SLACK_MS => true,
+            due => {
+                // Starts over after a stall instead of running a burst of
+                // frames to catch up.
+                let due = due.unwrap_or(now).max(now - period);
+                self.next_frame_due_ms = Some(due + period);
+                false
+            }
+        }
+    }
+
+    fn tick(
+        &mut self,
+        engine: &mut EngineState,
+        game: &mut impl GameLoop,
+    ) -> ControlFlow {
+        let mut control_flow = ControlFlow::Poll;
+
+        if let LoopMode::Reactive { max_idle } = game_config().loop_mode {
+            // Set before reading the redraw time, so requests from other
+            // threads that arrive in between still wake the loop.
+            set_loop_waiting(true);
+
+            let idle_until = max_idle.and_then(|max_idle| {
+                self.last_frame.checked_add(Duration::from_secs_f32(max_idle))
+            });
+
+            let wake_at = match (next_redraw(false), idle_until) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+
+            let due =
+                matches!(wake_at, Some(wake_at) if wake_at <= Instant::now());
+
+            if !self.had_events && !due {
+                return match wake_at {
+                    Some(wake_at) => ControlFlow::WaitUntil(wake_at),
+                    None => ControlFlow::Wait,
+                };
+            }
+
+            self.had_events = false;
+            let _ = next_redraw(true);
+        }
+
+        set_loop_waiting(false);
+
+        #[cfg(target_arch = "wasm32")]
+        if self.skip_browser_frame() {
+            return control_flow;
+        }
+
+        self.last_frame = Instant::now();
+
+        let _span = span!("frame with vsync");
+
+        if self.monitor_changed {
+            if let Some(renderer) = engine.renderer.as_ref() {
+                refresh_monitors(&renderer.window);
+            }
+        }
+
+        if is_target_framerate_auto() &&
+            (self.monitor_changed || !self.was_framerate_auto)
+        {
+            if let Some(renderer) = engine.renderer.as_ref() {
+                let previous = target_framerate();
+                set_detected_refresh_rate(monitor_refresh_rate(
+                    &renderer.window,
+                ));
+
+                if target_framerate() != previous || !self.was_framerate_auto {
+                    info!(
+                        "Framerate limit follows the monitor: {} FPS",
+                        target_framerate()
+                    );
                 }
+            }
+        }
 
-                set_frame_time(frame_start.elapsed().as_secs_f32());
-                inc_frame_num();
+        self.monitor_changed = false;
+        self.was_framerate_auto = is_target_framerate_auto();
 
-                let _span = span!("loop_sleep");
-                #[cfg(not(target_arch = "wasm32"))]
-                loop_helper.loop_sleep();
-                delta = frame_start.elapsed().as_secs_f32();
-                delta = delta.clamp(1.0 / 5000.0, 1.0 / 10.0);
+        #[cfg(not(target_arch = "wasm32"))]
+        if target_framerate() != self.applied_framerate {
+            self.applied_framerate = target_framerate();
+            self.loop_helper
+                .set_target_rate(PacingConfig::rate(self.applied_framerate));
+        }
 
-                #[cfg(feature = "tracy")]
-                tracy_client::frame_mark();
+        #[cfg(not(target_arch = "wasm32"))]
+        let (loop_helper, frame_limited) =
+            match self.unfocused_loop_helper.as_mut() {
+                Some(helper) if !is_window_focused() => (helper, true),
+                _ => (&mut self.loop_helper, self.applied_framerate > 0.0),
+            };
+        #[cfg(not(target_arch = "wasm32"))]
+        let _ = loop_helper.loop_start();
+        let frame_start = Instant::now();
+
+        // The browser runs frames at the display refresh rate and there's no
+        // sleep inside the frame like on desktop, so the delta is the time
+        // between frame starts rather than how long the frame took.
+        #[cfg(target_arch = "wasm32")]
+        let frame_period = {
+            let now = performance_now();
+            let period =
+                self.last_frame_start_ms.map(|last| (now - last) / 1000.0);
+            self.last_frame_start_ms = Some(now);
+            period
+        };
+
+        if let Some(size) = self.pending_resize.take() {
+            engine.resize(size);
+            update_safe_area_insets();
+            game.on_resize(size, &mut engine.make_context());
+        }
+
+        if let Some(scale_factor) = self.pending_scale_factor.take() {
+            game.on_scale_factor_changed(
+                scale_factor,
+                &mut engine.make_context(),
+            );
+        }
+
+        self.gamepads.poll();
+        update_input_recording();
+        engine.capture_input();
+
+        engine.apply_commands();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if engine.renderer.as_ref().is_some_and(|x| x.is_device_lost()) {
+            engine.recover_lost_device();
+            game.on_device_lost(&mut engine.make_context());
+        }
+
+        let engine_paused = is_engine_paused() ||
+            (game_config().pause_on_focus_loss && !is_window_focused());
+        let skip_update =
+            engine.frame == 0 && game_config().skip_first_frame_update;
+        let frame_delta = if engine_paused || skip_update {
+            0.0
+        } else {
+            self.delta * time_scale() as f64
+        };
+
+        set_real_delta(self.delta as f32);
+        advance_frame_time(frame_delta);
+        let frame_delta = frame_delta as f32;
+        use_default_shader();
+
+        if engine.quit_flag {
+            quit();
+        }
+
+        if game_config().quit_on_escape &&
+            is_key_pressed_this_frame(KeyCode::Escape)
+        {
+            quit_with_reason(QuitReason::Escape);
+        }
+
+        if should_quit() {
+            engine.quit_flag = true;
+            control_flow = ControlFlow::Exit;
+        }
+
+        {
+            span_with_timing!("frame");
+            let renderer = engine.renderer.as_mut().unwrap();
+
+            if let Some(egui_winit) = renderer.egui_winit.as_mut() {
+                let _span = span!("begin_frame");
+
+                // Picks up `set_ui_scale` changes.
+                egui_winit.update_pixels_per_point(egui(), &renderer.window);
+
+                let mut input = egui_winit.take_egui_input(&renderer.window);
+
+                let config = game_config();
+
+                if config.gamepad_ui_nav {
+                    input.events.extend(
+                        self.gamepad_ui_nav
+                            .events(&config.gamepad_ui_nav_mapping),
+                    );
+                }
+
+                drop(config);
+                egui().begin_frame(input);
             }
 
-            Event::WindowEvent { ref event, window_id: _ } => {
-                if engine.renderer.as_mut().unwrap().on_event(event, egui()) {
-                    return;
+            engine.frame += 1;
+            set_frame_count(engine.frame);
+
+            // All internal engine code expect an `EngineContext`.
+            let mut c = engine.make_context();
+            let mut stages = StageTimer::start();
+
+            run_early_update_stages(&mut c);
+            stages.early_update_done();
+
+            if !skip_update {
+                run_game_update(
+                    game,
+                    &mut c,
+                    engine_paused,
+                    &mut self.fixed_accumulator,
+                    &mut self.update_accumulator,
+                );
+            }
+
+            stages.game_update_done();
+
+            update_perf_counters(&mut c, game);
+            run_late_update_stages(&mut c, game, frame_delta);
+            stages.late_update_done();
+            engine.run_deferred();
+
+            engine.render_secondary_windows(game);
+        }
+
+        clear_frame_input();
+
+        set_frame_time(frame_start.elapsed().as_secs_f32());
+        inc_frame_num();
+
+        let _span = span!("loop_sleep");
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.benchmark.is_none() &&
+            frame_limited &&
+            !(present_mode() == Some(PresentMode::Fifo) &&
+                game_config().fifo_disables_frame_limiter)
+        {
+            self.pacing.sleep(loop_helper);
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.delta = frame_start.elapsed().as_secs_f64();
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            self.delta = frame_period.unwrap_or(self.delta);
+        }
+        frame_stats_mut().push(self.delta as f32);
+
+        if self.benchmark.is_some_and(|x| engine.frame == x.frames) {
+            println!("Benchmark: {}", frame_stats().report());
+            quit();
+        }
+
+        // Throttled frames are slow on purpose.
+        if is_window_focused() || game_config().unfocused_framerate.is_none() {
+            self.hitch_logger.check(self.delta as f32);
+        }
+
+        self.delta = match game_config().fixed_frame_delta {
+            Some(fixed_delta) => fixed_delta as f64,
+            None => {
+                let (min_delta, max_delta) = delta_clamp_range();
+                let (min_delta, max_delta) =
+                    (min_delta as f64, max_delta as f64);
+                let full_delta = self.delta + self.carried_delta;
+                let overflow = (full_delta - max_delta).max(0.0);
+
+                if game_config().accumulate_dropped_time {
+                    self.carried_delta = overflow;
+                } else {
+                    self.carried_delta = 0.0;
+                    add_dropped_time(overflow);
                 }
 
-                match event {
-                    WindowEvent::KeyboardInput {
-                        input: KeyboardInput { state, virtual_keycode, .. },
-                        ..
-                    } => {
-                        if let Some(keycode) =
-                            virtual_keycode.and_then(KeyCode::try_from_winit)
-                        {
-                            match state {
-                                ElementState::Pressed => {
-                                    let mut state = GLOBAL_STATE.borrow_mut();
-
-                                    state.pressed.insert(keycode);
-                                    state.just_pressed.insert(keycode);
-                                    state.just_released.remove(&keycode);
-                                }
-
-                                ElementState::Released => {
-                                    let mut state = GLOBAL_STATE.borrow_mut();
-
-                                    state.pressed.remove(&keycode);
-                                    state.just_pressed.remove(&keycode);
-                                    state.just_released.insert(keycode);
-                                }
-                            }
-                        }
-                    }
+                full_delta.clamp(min_delta, max_delta)
+            }
+        };
 
-                    WindowEvent::CursorMoved { position, .. } => {
-                        GLOBAL_STATE.borrow_mut().mouse_position =
-                            vec2(position.x as f32, position.y as f32);
-                    }
+        // Don't pass the (often long) first frame on to the first update when
+        // it was skipped.
+        if skip_update && game_config().fixed_frame_delta.is_none() {
+            let (min_delta, max_delta) = delta_clamp_range();
+            self.delta =
+                game_config().initial_delta.clamp(min_delta, max_delta) as f64;
+            self.carried_delta = 0.0;
+        }
 
-                    WindowEvent::MouseInput { state, button, .. } => {
-                        let quad_button = match button {
-                            winit::event::MouseButton::Left => {
-                                MouseButton::Left
-                            }
-                            winit::event::MouseButton::Right => {
-                                MouseButton::Right
-                            }
-                            winit::event::MouseButton::Middle => {
-                                MouseButton::Middle
-                            }
-                            winit::event::MouseButton::Other(num) => {
-                                MouseButton::Other(*num)
-                            }
-                        };
-
-                        let mut global_state = GLOBAL_STATE.borrow_mut();
-
-                        match state {
-                            ElementState::Pressed => {
-                                global_state.mouse_pressed.insert(quad_button);
-                                global_state
-                                    .mouse_just_pressed
-                                    .insert(quad_button);
-                            }
-                            ElementState::Released => {
-                                global_state.mouse_pressed.remove(&quad_button);
-                                global_state
-                                    .mouse_just_pressed
-                                    .remove(&quad_button);
-                                global_state
-                                    .mouse_just_released
-                                    .insert(quad_button);
-                            }
-                        }
-                    }
+        #[cfg(feature = "tracy")]
+        tracy_client::frame_mark();
 
-                    WindowEvent::MouseWheel { delta, .. } => {
-                        let mut global_state = GLOBAL_STATE.borrow_mut();
-
-                        match delta {
-                            MouseScrollDelta::LineDelta(x, y) => {
-                                global_state.mouse_wheel = (*x, *y);
-                            }
-                            MouseScrollDelta::PixelDelta(delta) => {
-                                error!(
-                                    "MouseScrollDelta::PixelDelta not \
-                                     implemented! {:?}",
-                                    delta
-                                );
-                            }
+        control_flow
+    }
+
+    fn handle_window_event(
+        &mut self,
+        engine: &mut EngineState,
+        game: &mut impl GameLoop,
+        window_id: winit::window::WindowId,
+        event: &WindowEvent,
+    ) {
+        if engine.handle_secondary_window_event(window_id, event) {
+            self.had_events = true;
+            return;
+        }
+
+        let is_main_window = engine
+            .renderer
+            .as_ref()
+            .is_some_and(|x| x.window.id() == window_id);
+
+        if !is_main_window {
+            return;
+        }
+
+        self.had_events = true;
+
+        if engine.renderer.as_mut().unwrap().on_event(event, egui()) {
+            return;
+        }
+
+        match event {
+            WindowEvent::KeyboardInput {
+                input: KeyboardInput { state, virtual_keycode, scancode, .. },
+                ..
+            } => {
+                handle_scancode(ScanCode(*scancode), *state);
+
+                if let Some(keycode) =
+                    virtual_keycode.and_then(KeyCode::try_from_winit)
+                {
+                    match state {
+                        ElementState::Pressed => {
+                            log_input_event(InputEventKind::KeyPressed(
+                                keycode,
+                            ));
+
+                            press_key(&mut GLOBAL_STATE.borrow_mut(), keycode);
                         }
-                    }
 
-                    WindowEvent::Resized(physical_size) => {
-                        if physical_size.width > min_resolution.0 &&
-                            physical_size.height > min_resolution.1
-                        {
-                            engine.resize(uvec2(
-                                physical_size.width,
-                                physical_size.height,
+                        ElementState::Released => {
+                            log_input_event(InputEventKind::KeyReleased(
+                                keycode,
                             ));
+
+                            release_key(
+                                &mut GLOBAL_STATE.borrow_mut(),
+                                keycode,
+                            );
                         }
                     }
+                }
+            }
+
+            WindowEvent::ReceivedCharacter(ch) if !ch.is_control() => {
+                if self.ime_composing {
+                    return;
+                }
+
+                if self.ime_committed.front() == Some(ch) {
+                    self.ime_committed.pop_front();
+                    return;
+                }
+
+                self.ime_committed.clear();
+                GLOBAL_STATE.borrow_mut().text_input.push(*ch);
+            }
 
-                    WindowEvent::ScaleFactorChanged {
-                        new_inner_size, ..
-                    } => {
-                        engine.resize(uvec2(
-                            new_inner_size.width,
-                            new_inner_size.height,
-                        ));
+            WindowEvent::Ime(winit::event::Ime::Preedit(text, _)) => {
+                self.ime_composing = !text.is_empty();
+            }
+
+            WindowEvent::Ime(winit::event::Ime::Commit(text)) => {
+                self.ime_composing = false;
+
+                let text = text.chars().filter(|x| !x.is_control());
+                self.ime_committed = text.clone().collect();
+                GLOBAL_STATE.borrow_mut().text_input.extend(text);
+            }
+
+            WindowEvent::Ime(winit::event::Ime::Disabled) => {
+                self.ime_composing = false;
+                self.ime_committed.clear();
+            }
+
+            WindowEvent::CursorMoved { position, .. } => {
+                let position = confine_cursor(
+                    &engine.renderer.as_ref().unwrap().window,
+                    vec2(position.x as f32, position.y as f32),
+                );
+
+                log_input_event(InputEventKind::MouseMoved(position));
+
+                let mut global_state = GLOBAL_STATE.borrow_mut();
+                global_state.mouse_position = position;
+                global_state.mouse_moved = true;
+            }
+
+            WindowEvent::MouseInput { state, button, .. } => {
+                let quad_button = MouseButton::from_winit(*button);
+
+                log_input_event(match state {
+                    ElementState::Pressed => {
+                        InputEventKind::MousePressed(quad_button)
+                    }
+                    ElementState::Released => {
+                        InputEventKind::MouseReleased(quad_button)
                     }
+                });
+
+                let mut global_state = GLOBAL_STATE.borrow_mut();
 
-                    WindowEvent::CloseRequested => {
-                        *control_flow = ControlFlow::Exit;
+                match state {
+                    ElementState::Pressed => {
+                        press_mouse_button(&mut global_state, quad_button);
+                    }
+                    ElementState::Released => {
+                        release_mouse_button(&mut global_state, quad_button);
                     }
-                    _ => {}
+                }
+            }
+
+            WindowEvent::Touch(touch) => {
+                handle_touch(touch);
+            }
+
+            WindowEvent::MouseWheel { delta, .. } => {
+                let (x, y) = scroll_delta_to_lines(
+                    delta,
+                    game_config().scroll_pixels_per_line,
+                );
+
+                log_input_event(InputEventKind::MouseWheel(x, y));
+
+                let mut global_state = GLOBAL_STATE.borrow_mut();
+                global_state.mouse_wheel.0 += x;
+                global_state.mouse_wheel.1 += y;
+            }
+
+            WindowEvent::HoveredFile(path) => {
+                GLOBAL_STATE.borrow_mut().hovered_files.insert(path.clone());
+            }
+
+            WindowEvent::HoveredFileCancelled => {
+                GLOBAL_STATE.borrow_mut().hovered_files.clear();
+            }
+
+            WindowEvent::DroppedFile(path) => {
+                let mut global_state = GLOBAL_STATE.borrow_mut();
+                global_state.hovered_files.remove(path);
+                global_state.dropped_files.push(path.clone());
+            }
+
+            WindowEvent::Resized(physical_size) => {
+                log_input_event(InputEventKind::Resized(uvec2(
+                    physical_size.width,
+                    physical_size.height,
+                )));
+
+                let window = &engine.renderer.as_ref().unwrap().window;
+                update_window_geometry(window);
+
+                let minimized =
+                    physical_size.width == 0 || physical_size.height == 0;
+
+                GLOBAL_STATE.borrow_mut().window_minimized = minimized;
+
+                let size = constrain_aspect_ratio(
+                    window,
+                    uvec2(physical_size.width, physical_size.height),
+                );
+
+                if size.x > self.min_resolution.0 &&
+                    size.y > self.min_resolution.1
+                {
+                    self.pending_resize = Some(size);
+
+                    if game_config().render_during_resize {
+                        engine
+                            .renderer
+                            .as_ref()
+                            .unwrap()
+                            .window
+                            .request_redraw();
+                    }
+                }
+            }
+
+            WindowEvent::Moved(_) => {
+                self.monitor_changed = true;
+                update_window_geometry(
+                    &engine.renderer.as_ref().unwrap().window,
+                );
+            }
+
+            WindowEvent::ScaleFactorChanged {
+                scale_factor,
+                new_inner_size,
+            } => {
+                GLOBAL_STATE.borrow_mut().window_scale_factor =
+                    *scale_factor as f32;
+                self.pending_scale_factor = Some(*scale_factor as f32);
+                self.monitor_changed = true;
+                update_window_geometry(
+                    &engine.renderer.as_ref().unwrap().window,
+                );
+
+                if new_inner_size.width > 0 && new_inner_size.height > 0 {
+                    self.pending_resize = Some(uvec2(
+                        new_inner_size.width,
+                        new_inner_size.height,
+                    ));
+                }
+            }
+
+            WindowEvent::Focused(focused) => {
+                GLOBAL_STATE.borrow_mut().window_focused = *focused;
+
+                if !focused && cursor_confine_rect().is_some() {
+                    set_cursor_confine_rect(
+                        &engine.renderer.as_ref().unwrap().window,
+                        None,
+                    );
+                }
+            }
+
+            WindowEvent::CloseRequested => {
+                let mut c = engine.make_context();
+
+                if game.on_close_requested(&mut c) == CloseResponse::Exit {
+                    quit_with_reason(QuitReason::WindowClosed);
                 }
             }
             _ => {}
         }
+    }
+}
+
+/// Initial window position from `COMFY_WINDOW_X`/`COMFY_WINDOW_Y` (in
+/// physical pixels) and `COMFY_MONITOR` (index into the available monitors).
+/// With a monitor the position is relative to it, and without a position the
+/// window is placed at the monitor's top left corner. Invalid values are
+/// ignored.
+fn window_position_from_env(
+    event_loop: &winit::event_loop::EventLoop<ComfyUserEvent>,
+) -> Option<winit::dpi::PhysicalPosition<i32>> {
+    use std::env::var;
+
+    let parse = |name: &str| var(name).ok().and_then(|x| x.parse::<i32>().ok());
+
+    let offset = match (parse("COMFY_WINDOW_X"), parse("COMFY_WINDOW_Y")) {
+        (Some(x), Some(y)) => Some(ivec2(x, y)),
+        _ => None,
+    };
+
+    let monitor = var("COMFY_MONITOR")
+        .ok()
+        .and_then(|x| x.parse::<usize>().ok())
+        .and_then(|index| event_loop.available_monitors().nth(index));
+
+    let origin = monitor.map(|monitor| {
+        let position = monitor.position();
+        ivec2(position.x, position.y)
+    });
+
+    let position = match (origin, offset) {
+        (None, None) => return None,
+        (origin, offset) => {
+            origin.unwrap_or(IVec2::ZERO) + offset.unwrap_or(IVec2::ZERO)
+        }
+    };
+
+    Some(winit::dpi::PhysicalPosition::new(position.x, position.y))
+}
+
+/// Picks a window size with the aspect ratio of `design`, at most the design
+/// resolution itself and shrunk to fit comfortably on the monitor.
+fn initial_aspect_window_size(
+    design: (u32, u32),
+    monitor_size: Option<UVec2>,
+) -> UVec2 {
+    let design = vec2(design.0.max(1) as f32, design.1.max(1) as f32);
+
+    let scale = match monitor_size {
+        Some(monitor) => {
+            let available = monitor.as_vec2() * 0.8;
+            (available.x / design.x).min(available.y / design.y).min(1.0)
+        }
+        None => 1.0,
+    };
+
+    (design * scale).round().as_uvec2().max(UVec2::ONE)
+}
+
+/// Snaps the window to `GameConfig::aspect_ratio` after it was resized to
+/// `size`, returning the size it will end up with.
+fn constrain_aspect_ratio(window: &Window, size: UVec2) -> UVec2 {
+    let Some(ratio) = game_config().aspect_ratio.filter(|x| *x > 0.0) else {
+        return size;
+    };
+
+    if size.x == 0 ||
+        size.y == 0 ||
+        window.is_maximized() ||
+        window.fullscreen().is_some()
+    {
+        return size;
+    }
+
+    let fitted = fit_aspect_ratio(size, ratio);
+
+    if fitted != size {
+        window
+            .set_inner_size(winit::dpi::PhysicalSize::new(fitted.x, fitted.y));
+    }
+
+    fitted
+}
+
+/// Runs the game's part of a frame, i.e. either `paused_update` or the fixed
+/// updates followed by `update`.
+pub(crate) fn run_game_update(
+    game: &mut impl GameLoop,
+    c: &mut EngineContext,
+    engine_paused: bool,
+    fixed_accumulator: &mut f32,
+    update_accumulator: &mut f32,
+) {
+    let freeze_frame = game_config().freeze_frame_on_pause;
+
+    if engine_paused {
+        if freeze_frame {
+            redraw_frozen_frame();
+        }
+
+        game.paused_update(c);
+
+        // The latching modes would otherwise keep presses from the pause menu
+        // around until the first tick after unpausing.
+        if game_config().just_pressed_mode != JustPressedMode::PerFrame {
+            clear_just_pressed();
+        }
+    } else {
+        let draws_before = queued_draw_counts();
+
+        let delta = c.delta;
+        run_fixed_updates(game, c, fixed_accumulator, delta);
+        run_updates(game, c, update_accumulator, delta);
+
+        if freeze_frame {
+            freeze_frame_draws(draws_before);
+        }
+    }
+
+    game.render_update(c);
+}
+
+fn queued_draw_counts() -> (usize, usize) {
+    let state = GLOBAL_STATE.borrow();
+    (state.mesh_queue.len(), state.text_queue.len())
+}
+
+/// Keeps a copy of what the game drew since `queued_draw_counts` returned
+/// `start`, see `GameConfig::freeze_frame_on_pause`.
+fn freeze_frame_draws(start: (usize, usize)) {
+    let mut state = GLOBAL_STATE.borrow_mut();
+    let state = &mut *state;
+
+    state.frozen_mesh_queue.clear();
+    state
+        .frozen_mesh_queue
+        .extend_from_slice(state.mesh_queue.get(start.0..).unwrap_or(&[]));
+
+    state.frozen_text_queue.clear();
+    state
+        .frozen_text_queue
+        .extend_from_slice(state.text_queue.get(start.1..).unwrap_or(&[]));
+}
+
+fn redraw_frozen_frame() {
+    let mut state = GLOBAL_STATE.borrow_mut();
+    let state = &mut *state;
+
+    state.mesh_queue.extend_from_slice(&state.frozen_mesh_queue);
+    state.text_queue.extend_from_slice(&state.frozen_text_queue);
+}
+
+/// Clears the per-frame input state (just pressed/released, scroll) at the
+/// end of a frame.
+pub(crate) fn clear_frame_input() {
+    let clear = {
+        let config = game_config();
+        clears_just_pressed_per_frame(
+            config.just_pressed_mode,
+            config.fixed_timestep,
+        )
+    };
+
+    if clear {
+        clear_just_pressed();
+    }
+
+    let mut global_state = GLOBAL_STATE.borrow_mut();
+    global_state.mouse_wheel = (0.0, 0.0);
+    global_state.mouse_delta = Vec2::ZERO;
+    global_state.mouse_moved = false;
+    global_state.frame_pressed.clear();
+
+    global_state.touches.retain(|_, touch| {
+        !matches!(touch.phase, TouchPhase::Ended | TouchPhase::Cancelled)
+    });
+}
+
+/// Whether `clear_frame_input` clears the just pressed state. Latching until
+/// a fixed update falls back to clearing every frame when fixed updates are
+/// disabled, since no tick would ever clear it.
+fn clears_just_pressed_per_frame(
+    mode: JustPressedMode,
+    fixed_timestep: f32,
+) -> bool {
+    match mode {
+        JustPressedMode::PerFrame => true,
+        JustPressedMode::UntilUpdate => false,
+        JustPressedMode::UntilFixedUpdate => fixed_timestep <= 0.0,
+    }
+}
+
+/// Clears the just pressed/released state, either at the end of the frame or
+/// after the tick that consumed it, see `GameConfig::just_pressed_mode`.
+fn clear_just_pressed() {
+    let mut global_state = GLOBAL_STATE.borrow_mut();
+    global_state.just_pressed.clear();
+    global_state.just_released.clear();
+    global_state.just_pressed_scancodes.clear();
+    global_state.just_released_scancodes.clear();
+    global_state.mouse_just_pressed.clear();
+    global_state.mouse_just_released.clear();
+
+    for gamepad in global_state.gamepads.values_mut() {
+        gamepad.just_pressed.clear();
+        gamepad.just_released.clear();
+    }
+}
+
+fn handle_scancode(scancode: ScanCode, element_state: ElementState) {
+    let mut state = GLOBAL_STATE.borrow_mut();
+
+    match element_state {
+        ElementState::Pressed => {
+            state.pressed_scancodes.insert(scancode);
+            state.just_released_scancodes.remove(&scancode);
+
+            if !(game_config().block_input_under_ui &&
+                egui().wants_keyboard_input())
+            {
+                state.just_pressed_scancodes.insert(scancode);
+            }
+        }
+
+        ElementState::Released => {
+            state.pressed_scancodes.remove(&scancode);
+            state.just_pressed_scancodes.remove(&scancode);
+            state.just_released_scancodes.insert(scancode);
+        }
+    }
+}
+
+fn handle_touch(touch: &winit::event::Touch) {
+    use winit::event::TouchPhase as Phase;
+
+    let phase = match touch.phase {
+        Phase::Started => TouchPhase::Started,
+        Phase::Moved => TouchPhase::Moved,
+        Phase::Ended => TouchPhase::Ended,
+        Phase::Cancelled => TouchPhase::Cancelled,
+    };
+
+    let position = vec2(touch.location.x as f32, touch.location.y as f32);
+
+    let mut global_state = GLOBAL_STATE.borrow_mut();
+
+    global_state.touches.insert(touch.id, TouchPoint {
+        id: touch.id,
+        phase,
+        position,
     });
+
+    if !game_config().touch_emulates_mouse {
+        return;
+    }
+
+    if phase == TouchPhase::Started && global_state.primary_touch.is_none() {
+        global_state.primary_touch = Some(touch.id);
+        global_state.mouse_position = position;
+        global_state.mouse_moved = true;
+        press_mouse_button(&mut global_state, MouseButton::Left);
+    } else if global_state.primary_touch == Some(touch.id) {
+        global_state.mouse_position = position;
+        global_state.mouse_moved = true;
+
+        if matches!(phase, TouchPhase::Ended | TouchPhase::Cancelled) {
+            global_state.primary_touch = None;
+            release_mouse_button(&mut global_state, MouseButton::Left);
+        }
+    }
+}
+
+/// Calls `GameLoop::update` once, or as often as `GameConfig::update_rate`
+/// asks for.
+fn run_updates(
+    game: &mut impl GameLoop,
+    c: &mut EngineContext,
+    accumulator: &mut f32,
+    delta: f32,
+) {
+    let latch = game_config().just_pressed_mode == JustPressedMode::UntilUpdate;
+
+    let (interval, max_steps) = {
+        let config = game_config();
+
+        match config.update_rate {
+            Some(rate) if rate > 0.0 => {
+                (1.0 / rate, config.max_fixed_steps_per_frame)
+            }
+            _ => {
+                drop(config);
+                set_update_alpha(1.0);
+                game.update(c);
+
+                if latch {
+                    clear_just_pressed();
+                }
+
+                return;
+            }
+        }
+    };
+
+    *accumulator += delta;
+
+    let mut steps = 0;
+
+    // `update` sees the length of an update as its delta.
+    set_delta(interval);
+    c.delta = interval;
+
+    while *accumulator >= interval && steps < max_steps {
+        let _span = span!("update");
+        game.update(c);
+
+        if latch {
+            clear_just_pressed();
+        }
+
+        *accumulator -= interval;
+        steps += 1;
+    }
+
+    set_delta(delta);
+    c.delta = delta;
+
+    if steps == max_steps {
+        *accumulator = accumulator.min(interval);
+    }
+
+    set_update_alpha((*accumulator / interval).clamp(0.0, 1.0));
+}
+
+fn run_fixed_updates(
+    game: &mut impl GameLoop,
+    c: &mut EngineContext,
+    accumulator: &mut f32,
+    delta: f32,
+) {
+    let (timestep, max_steps, latch) = {
+        let config = game_config();
+        (
+            config.fixed_timestep,
+            config.max_fixed_steps_per_frame,
+            config.just_pressed_mode == JustPressedMode::UntilFixedUpdate,
+        )
+    };
+
+    if timestep <= 0.0 {
+        return;
+    }
+
+    set_fixed_delta(timestep);
+    *accumulator += delta;
+
+    let mut steps = 0;
+
+    while *accumulator >= timestep && steps < max_steps {
+        let _span = span!("fixed_update");
+        game.fixed_update(c);
+
+        if latch {
+            clear_just_pressed();
+        }
+
+        *accumulator -= timestep;
+        steps += 1;
+    }
+
+    // We couldn't catch up, drop the backlog instead of accumulating it
+    // forever.
+    if steps == max_steps {
+        *accumulator = accumulator.min(timestep);
+    }
+
+    set_fixed_alpha((*accumulator / timestep).clamp(0.0, 1.0));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_update_latch_needs_fixed_updates() {
+        let mode = JustPressedMode::UntilFixedUpdate;
+
+        assert!(!clears_just_pressed_per_frame(mode, 1.0 / 60.0));
+        // Without fixed ticks nothing else would ever clear the presses.
+        assert!(clears_just_pressed_per_frame(mode, 0.0));
+
+        assert!(clears_just_pressed_per_frame(JustPressedMode::PerFrame, 0.0));
+        assert!(!clears_just_pressed_per_frame(
+            JustPressedMode::UntilUpdate,
+            0.0
+        ));
+    }
 }
@@ -0,0 +1,110 @@
+use crate::*;
+
+#[derive(Copy, Clone, Debug)]
+pub struct HeadlessConfig {
+    /// Number of frames to simulate before returning.
+    pub ticks: u64,
+    /// Delta used for every simulated frame.
+    pub fixed_dt: f32,
+}
+
+impl Default for HeadlessConfig {
+    fn default() -> Self {
+        Self { ticks: 60, fixed_dt: 1.0 / 60.0 }
+    }
+}
+
+/// Runs the game loop without a window or a GPU device, which is useful for
+/// integration tests and dedicated servers.
+///
+/// Every frame uses `config.fixed_dt` as its delta so runs are deterministic.
+/// Draw calls are still accepted, but nothing is rendered. Returns after
+/// `config.ticks` frames or as soon as `quit()` is called or `quit_flag` is
+/// set, calling `GameLoop::on_exit` before returning.
+///
+/// `init_game_config` must be called before this, same as for the regular
+/// game loop.
+pub fn run_comfy_headless(
+    game: &mut impl GameLoop,
+    engine: &mut EngineState,
+    config: HeadlessConfig,
+) {
+    let mut accumulators = HeadlessAccumulators::default();
+
+    start_real_time();
+    frame_stats_mut().set_capacity(game_config().frame_stats_window);
+
+    for _ in 0..config.ticks {
+        if engine.quit_flag || should_quit() {
+            break;
+        }
+
+        run_headless_frame(game, engine, config.fixed_dt, &mut accumulators);
+    }
+
+    let mut c = engine.make_context();
+    game.on_exit(&mut c);
+}
+
+/// Time left over for the next `fixed_update` and `update` ticks, carried
+/// between headless frames.
+#[derive(Copy, Clone, Debug, Default)]
+pub(crate) struct HeadlessAccumulators {
+    fixed: f32,
+    update: f32,
+}
+
+/// Runs a single frame of `run_comfy_headless` with a delta of `dt`.
+pub(crate) fn run_headless_frame(
+    game: &mut impl GameLoop,
+    engine: &mut EngineState,
+    dt: f32,
+    accumulators: &mut HeadlessAccumulators,
+) {
+    update_input_recording();
+    engine.capture_input();
+    engine.apply_commands();
+
+    let engine_paused = is_engine_paused();
+    let frame_delta = if engine_paused { 0.0 } else { dt * time_scale() };
+
+    set_real_delta(dt);
+    advance_frame_time(frame_delta as f64);
+    use_default_shader();
+
+    egui().begin_frame(egui::RawInput::default());
+
+    engine.frame += 1;
+    set_frame_count(engine.frame);
+
+    {
+        let mut c = engine.make_context();
+        let mut stages = StageTimer::start();
+
+        run_early_update_stages(&mut c);
+        stages.early_update_done();
+
+        run_game_update(
+            game,
+            &mut c,
+            engine_paused,
+            &mut accumulators.fixed,
+            &mut accumulators.update,
+        );
+        stages.game_update_done();
+
+        run_late_update_stages(&mut c, game, frame_delta);
+        stages.late_update_done();
+    }
+
+    engine.run_deferred();
+
+    // Nothing renders the egui output when running headless.
+    let _ = egui().end_frame();
+
+    clear_frame_input();
+
+    set_frame_time(dt);
+    frame_stats_mut().push(dt);
+    inc_frame_num();
+}
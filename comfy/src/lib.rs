@@ -14,10 +14,12 @@ mod egui_utils;
 mod engine;
 mod game;
 mod game_loop;
+mod headless;
 mod macros;
 mod particles;
 mod render;
 mod shaders;
+mod test_harness;
 mod timer;
 mod trail;
 mod update_stages;
@@ -35,10 +37,12 @@ pub use crate::egui_utils::*;
 pub use crate::engine::*;
 pub use crate::game::*;
 pub use crate::game_loop::*;
+pub use crate::headless::*;
 // pub use crate::macros::*;
 pub use crate::particles::*;
 pub use crate::render::*;
 pub use crate::shaders::*;
+pub use crate::test_harness::*;
 pub use crate::timer::*;
 pub use crate::trail::*;
 pub use crate::update_stages::*;
@@ -0,0 +1,147 @@
+use crate::*;
+
+/// Drives a game frame by frame without a window, for testing how it reacts
+/// to input. Input is applied to the global input state right away and seen
+/// by the game on the next `tick`, the same way window events are.
+///
+/// ```ignore
+/// init_game_config("test".to_string(), "0.1", |config| config);
+///
+/// let mut harness = TestHarness::new(MyGame::default());
+///
+/// harness.hold_key(KeyCode::Space, 30);
+/// harness.tick();
+///
+/// assert!(harness.game.charged_jump);
+/// ```
+///
+/// Frames run exactly like in `run_comfy_headless`. Until `finish` is
+/// called, `real_time()` follows the simulated frames instead of the wall
+/// clock, so `key_held_time` during the last tick of `hold_key(key, 30)` is
+/// 30 ticks.
+///
+/// As the engine state is global, tests using a harness share it and
+/// shouldn't run in parallel, e.g. put them in their own integration test
+/// file or run them with `--test-threads=1`. `init_game_config` can only be
+/// called once per process.
+pub struct TestHarness<G: GameLoop> {
+    pub game: G,
+    pub engine: EngineState,
+    /// Delta of every tick, `1.0 / 60.0` by default.
+    pub dt: f32,
+    accumulators: HeadlessAccumulators,
+    /// Simulated `real_time()`.
+    time: f64,
+}
+
+impl<G: GameLoop> TestHarness<G> {
+    /// Expects `init_game_config` to have been called. Clears any input
+    /// left over from a previous harness.
+    pub fn new(game: G) -> Self {
+        set_simulated_real_time(Some(0.0));
+        frame_stats_mut().set_capacity(game_config().frame_stats_window);
+
+        {
+            let mut state = GLOBAL_STATE.borrow_mut();
+            state.pressed.clear();
+            state.pressed_scancodes.clear();
+            state.mouse_pressed.clear();
+            state.key_press_times.clear();
+        }
+
+        clear_frame_input();
+
+        Self {
+            game,
+            engine: EngineState::new(),
+            dt: HeadlessConfig::default().fixed_dt,
+            accumulators: HeadlessAccumulators::default(),
+            time: 0.0,
+        }
+    }
+
+    /// Runs a single frame, advancing `real_time()` by `dt` first.
+    pub fn tick(&mut self) {
+        self.time += self.dt as f64;
+        set_simulated_real_time(Some(self.time));
+
+        run_headless_frame(
+            &mut self.game,
+            &mut self.engine,
+            self.dt,
+            &mut self.accumulators,
+        );
+    }
+
+    pub fn tick_n(&mut self, frames: u32) {
+        for _ in 0..frames {
+            self.tick();
+        }
+    }
+
+    /// Context for inspecting the engine state between ticks.
+    pub fn context(&mut self) -> EngineContext<'_> {
+        self.engine.make_context()
+    }
+
+    pub fn press_key(&mut self, keycode: KeyCode) {
+        press_key(&mut GLOBAL_STATE.borrow_mut(), keycode);
+    }
+
+    pub fn release_key(&mut self, keycode: KeyCode) {
+        release_key(&mut GLOBAL_STATE.borrow_mut(), keycode);
+    }
+
+    /// Presses `keycode` for a single tick and releases it again. The
+    /// release is seen on the next tick.
+    pub fn tap_key(&mut self, keycode: KeyCode) {
+        self.hold_key(keycode, 1);
+    }
+
+    /// Presses `keycode`, keeps it down for `frames` ticks and releases it.
+    /// The release is seen on the next tick.
+    pub fn hold_key(&mut self, keycode: KeyCode, frames: u32) {
+        self.press_key(keycode);
+        self.tick_n(frames);
+        self.release_key(keycode);
+    }
+
+    pub fn press_mouse(&mut self, button: MouseButton) {
+        press_mouse_button(&mut GLOBAL_STATE.borrow_mut(), button);
+    }
+
+    pub fn release_mouse(&mut self, button: MouseButton) {
+        release_mouse_button(&mut GLOBAL_STATE.borrow_mut(), button);
+    }
+
+    /// Like `hold_key` for a mouse button.
+    pub fn hold_mouse(&mut self, button: MouseButton, frames: u32) {
+        self.press_mouse(button);
+        self.tick_n(frames);
+        self.release_mouse(button);
+    }
+
+    /// Moves the cursor to `position` in physical pixels, see
+    /// `mouse_screen()`. `mouse_world()` follows on the next tick.
+    pub fn move_mouse(&mut self, position: Vec2) {
+        let mut state = GLOBAL_STATE.borrow_mut();
+        state.mouse_position = position;
+        state.mouse_moved = true;
+    }
+
+    /// Scrolls by `x` and `y` lines, see `mouse_wheel()`.
+    pub fn scroll(&mut self, x: f32, y: f32) {
+        let mut state = GLOBAL_STATE.borrow_mut();
+        state.mouse_wheel.0 += x;
+        state.mouse_wheel.1 += y;
+    }
+
+    /// Runs `GameLoop::on_exit` and puts `real_time()` back on the wall
+    /// clock.
+    pub fn finish(mut self) -> G {
+        let mut c = self.engine.make_context();
+        self.game.on_exit(&mut c);
+        set_simulated_real_time(None);
+        self.game
+    }
+}
@@ -6,8 +6,17 @@ pub(crate) fn run_early_update_stages(c: &mut EngineContext) {
     {
         let mut state = GLOBAL_STATE.borrow_mut();
 
-        state.fps = (1.0 / delta).round() as i32;
+        state.fps = (1.0 / real_delta()).round() as i32;
         state.egui_scale_factor = egui().pixels_per_point();
+
+        let smoothing = game_config().mouse_smoothing;
+
+        state.mouse_position_smoothed = if smoothing > 0.0 {
+            let t = 1.0 - (-smoothing * real_delta()).exp();
+            state.mouse_position_smoothed.lerp(state.mouse_position, t)
+        } else {
+            state.mouse_position
+        };
     }
 
     dev_hotkeys(c);
@@ -25,10 +34,9 @@ pub(crate) fn run_early_update_stages(c: &mut EngineContext) {
     update_blood_canvas(c);
     update_camera(c);
 
-    // TODO: not ideal
-    clear_background(BLACK);
+    clear_background(game_config().clear_color);
 
-    if is_key_pressed(KeyCode::Backquote) &&
+    if is_key_pressed_this_frame(KeyCode::Backquote) &&
         is_key_down(KeyCode::LCtrl) &&
         is_key_down(KeyCode::LAlt)
     {
@@ -48,13 +56,17 @@ fn run_mid_update_stages(c: &mut EngineContext) {
     pause_system(c);
     point_lights_system();
 
-    if is_key_pressed(KeyCode::F6) {
+    if is_key_pressed_this_frame(KeyCode::F6) {
         GlobalParams::toggle_flag("debug");
     }
 }
 
 // TODO: Some of the ordering in the update stages is definitely incorrect.
-pub(crate) fn run_late_update_stages(c: &mut EngineContext, delta: f32) {
+pub(crate) fn run_late_update_stages(
+    c: &mut EngineContext,
+    game: &mut impl GameLoop,
+    delta: f32,
+) {
     update_animated_sprites(c);
     update_trails(c);
     update_drawables(c);
@@ -81,7 +93,7 @@ pub(crate) fn run_late_update_stages(c: &mut EngineContext, delta: f32) {
 
     player_follow_system();
     animated_sprite_builder_check();
-    renderer_update(c);
+    renderer_update(c, game);
 
     let is_paused =
         *c.is_paused.borrow() || c.flags.borrow_mut().contains(PAUSE_DESPAWN);
@@ -105,7 +117,7 @@ pub(crate) fn run_late_update_stages(c: &mut EngineContext, delta: f32) {
 
 fn dev_hotkeys(_c: &EngineContext) {
     // TODO: get rid of this & move it to nanovoid instead
-    if is_key_pressed(KeyCode::F1) {
+    if is_key_pressed_this_frame(KeyCode::F1) {
         let mut global_state = GLOBAL_STATE.borrow_mut();
         global_state.mouse_locked = !global_state.mouse_locked;
     }
@@ -115,14 +127,14 @@ fn dev_hotkeys(_c: &EngineContext) {
     //     .window()
     //     .set_cursor_visible(global_state.mouse_locked);
 
-    if is_key_pressed(KeyCode::F7) {
+    if is_key_pressed_this_frame(KeyCode::F7) {
         let mut config = game_config_mut();
 
         config.dev.show_lighting_config = !config.dev.show_lighting_config;
         config.dev.show_buffers = !config.dev.show_buffers;
     }
 
-    if is_key_pressed(KeyCode::F8) {
+    if is_key_pressed_this_frame(KeyCode::F8) {
         let mut config = game_config_mut();
 
         config.dev.show_fps = !config.dev.show_fps;
@@ -136,7 +148,7 @@ fn dev_hotkeys(_c: &EngineContext) {
     let _span = span!("game-state update");
 
     #[cfg(any(feature = "quick-exit", feature = "dev"))]
-    if is_key_down(KeyCode::F1) && is_key_pressed(KeyCode::Escape) {
+    if is_key_down(KeyCode::F1) && is_key_pressed_this_frame(KeyCode::Escape) {
         println!("fast exit");
         std::process::exit(0);
     }
@@ -153,8 +165,10 @@ fn process_asset_queues(c: &mut EngineContext) {
         ASSETS.borrow_mut().asset_loader.wgpu_load_queue.try_lock()
     {
         if let Some(batch) = guard.take() {
-            for item in batch.into_iter() {
-                c.renderer.loaded_image_send.send(item).log_err();
+            if let Some(renderer) = c.renderer.as_deref() {
+                for item in batch.into_iter() {
+                    renderer.loaded_image_send.send(item).log_err();
+                }
             }
         }
     }
@@ -184,7 +198,11 @@ fn render_text(c: &mut EngineContext) {
 
     for text in text_queue {
         if let Some(pro_params) = text.pro_params {
-            let mut t = c.renderer.text.borrow_mut();
+            let Some(renderer) = c.renderer.as_deref() else {
+                continue;
+            };
+
+            let mut t = renderer.text.borrow_mut();
 
             let (clean_text, styled_glyphs) = match text.text {
                 TextData::Raw(raw_text) => (raw_text, None),
@@ -419,21 +437,29 @@ fn update_camera(c: &mut EngineContext) {
     let mut global_state = GLOBAL_STATE.borrow_mut();
     let mut camera = main_camera_mut();
 
-    let width = c.renderer.width();
-    let height = c.renderer.height();
+    let (width, height) = match c.renderer.as_deref() {
+        Some(renderer) => (renderer.width(), renderer.height()),
+        None => {
+            let resolution = game_config().resolution;
+            (resolution.width() as f32, resolution.height() as f32)
+        }
+    };
 
-    camera.aspect_ratio = width / height;
     global_state.screen_size = vec2(width, height);
 
+    let screen = global_state
+        .viewport
+        .unwrap_or(Rect::from_xywh(0.0, 0.0, width, height));
+
+    camera.aspect_ratio = screen.size.x / screen.size.y;
+
     let viewport = camera.world_viewport();
 
-    let flipped_mouse_pos = vec2(
-        global_state.mouse_position.x,
-        global_state.screen_size.y - global_state.mouse_position.y,
-    );
+    let mouse_pos = global_state.mouse_position - screen.top_left();
+    let flipped_mouse_pos = vec2(mouse_pos.x, screen.size.y - mouse_pos.y);
 
-    let normalized = flipped_mouse_pos / global_state.screen_size * viewport -
-        viewport / 2.0;
+    let normalized =
+        flipped_mouse_pos / screen.size * viewport - viewport / 2.0;
 
     if !global_state.mouse_locked {
         global_state.mouse_world = normalized + camera.center;
@@ -514,7 +540,7 @@ fn update_animated_sprites(c: &mut EngineContext) {
 
 fn pause_system(c: &mut EngineContext) {
     // TODO: configurable pause
-    if is_key_pressed(KeyCode::Escape) {
+    if is_key_pressed_this_frame(KeyCode::Escape) {
         if *c.is_paused.borrow() && *c.show_pause_menu {
             info!("Resuming");
             *c.is_paused.borrow_mut() = false;
@@ -648,7 +674,7 @@ fn process_temp_draws(_c: &mut EngineContext) {
 fn process_notifications(_c: &mut EngineContext) {
     let notifications = notifications();
 
-    if !notifications.notifications.is_empty() {
+    if !notifications.notifications.is_empty() && game_config().enable_egui {
         egui::Window::new("Notifications")
             .anchor(egui::Align2::LEFT_TOP, egui::vec2(20.0, 280.0))
             .resizable(false)
@@ -694,7 +720,7 @@ fn process_notifications(_c: &mut EngineContext) {
 }
 
 fn show_errors(_c: &mut EngineContext) {
-    if cfg!(feature = "dev") {
+    if cfg!(feature = "dev") && game_config().enable_egui {
         let errors = ERRORS.borrow();
 
         if !errors.data.is_empty() {
@@ -713,7 +739,10 @@ fn show_errors(_c: &mut EngineContext) {
 
 #[doc(hidden)]
 pub fn update_perf_counters(c: &mut EngineContext, game_loop: &impl GameLoop) {
-    if cfg!(not(feature = "ci-release")) && game_config().dev.show_fps {
+    if cfg!(not(feature = "ci-release")) &&
+        game_config().dev.show_fps &&
+        game_config().enable_egui
+    {
         let _span = span!("perf counters");
 
         let dt = c.dt_stats.next(frame_time());
@@ -873,7 +902,7 @@ pub fn update_perf_counters(c: &mut EngineContext, game_loop: &impl GameLoop) {
 }
 
 pub fn lighting_parameters_window(_c: &EngineContext) {
-    if GlobalParams::flag("debug") {
+    if GlobalParams::flag("debug") && game_config().enable_egui {
         egui::Window::new("Parameters")
             .anchor(egui::Align2::LEFT_BOTTOM, egui::vec2(150.0, -80.0))
             .show(egui(), |ui| {
@@ -986,9 +1015,16 @@ fn animated_sprite_builder_check() {
     }
 }
 
-fn renderer_update(c: &mut EngineContext) {
+fn renderer_update(c: &mut EngineContext, game: &mut impl GameLoop) {
     let delta = delta();
 
+    // Nothing is drawn while minimized or headless.
+    let draws = c.renderer.is_some() && !is_window_minimized();
+
+    if draws {
+        game.pre_render(c);
+    }
+
     SINGLE_PARTICLES.borrow_mut().retain_mut(|particle| {
         particle.update(delta);
         particle.lifetime_current > 0.0
@@ -1052,28 +1088,53 @@ fn renderer_update(c: &mut EngineContext) {
 
     mesh_queue.sort_by_key(|x| x.mesh.z_index);
 
-    let mut draw_params = DrawParams {
-        aspect_ratio: aspect_ratio(),
-        config: &mut game_config_mut(),
-        projection: main_camera().build_view_projection_matrix(),
-        white_px: texture_path("1px"),
-        clear_color,
-        frame: frame_params,
-        lights: LightingState::take_lights(),
-        // sprite_queue,
-        mesh_queue,
-        particle_queue,
-        egui: egui(),
-    };
+    // The config stays borrowed for as long as `draw_params` lives, so it has
+    // to be gone before `post_render` and `present` read it again.
+    {
+        let mut draw_params = DrawParams {
+            aspect_ratio: aspect_ratio(),
+            config: &mut game_config_mut(),
+            projection: main_camera().build_view_projection_matrix(),
+            white_px: texture_path("1px"),
+            clear_color,
+            frame: frame_params,
+            lights: LightingState::take_lights(),
+            // sprite_queue,
+            mesh_queue,
+            particle_queue,
+            egui: egui(),
+        };
 
-    // TODO: cleanup unwraps and stuff :)
-    c.renderer.update(&mut draw_params);
-    c.renderer.draw(draw_params);
-    c.renderer.end_frame();
+        // TODO: cleanup unwraps and stuff :)
+        if is_window_minimized() {
+            // There's no surface to present to, but egui still has to finish
+            // its frame.
+            if draw_params.config.enable_egui {
+                let _ = egui().end_frame();
+            }
+        } else if let Some(renderer) = c.renderer.as_deref_mut() {
+            renderer.submit_command_encoder();
+            renderer.update(&mut draw_params);
+            renderer.draw(draw_params);
+        }
+    }
+
+    if draws {
+        game.post_render(c);
+    }
+
+    if let Some(renderer) = c.renderer.as_deref_mut() {
+        renderer.submit_command_encoder();
+
+        if !is_window_minimized() {
+            renderer.present(egui(), &game_config());
+            renderer.end_frame();
+        }
+    }
 }
 
 fn show_lighting_ui(_c: &mut EngineContext) {
-    if game_config().dev.show_lighting_config {
+    if game_config().dev.show_lighting_config && game_config().enable_egui {
         egui::Window::new("Lighting")
             .anchor(egui::Align2::LEFT_TOP, egui::vec2(0.0, 0.0))
             .show(egui(), |ui| {
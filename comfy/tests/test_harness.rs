@@ -0,0 +1,58 @@
+use comfy::*;
+
+const CHARGE_TIME: f32 = 0.5;
+
+#[derive(Default)]
+struct ChargedJump {
+    held_frames: u32,
+    charged_jumps: u32,
+    weak_jumps: u32,
+}
+
+impl GameLoop for ChargedJump {
+    fn new(_c: &mut EngineState) -> Self {
+        Self::default()
+    }
+
+    fn update(&mut self, _c: &mut EngineContext) {
+        if is_key_down(KeyCode::Space) {
+            self.held_frames += 1;
+
+            if key_held_time(KeyCode::Space) >= CHARGE_TIME - 0.001 &&
+                self.charged_jumps == 0
+            {
+                self.charged_jumps += 1;
+            }
+        }
+
+        if is_key_released(KeyCode::Space) && self.charged_jumps == 0 {
+            self.weak_jumps += 1;
+        }
+    }
+}
+
+// A single test, the harness drives global engine state.
+#[test]
+fn held_keys_follow_simulated_ticks() {
+    init_game_config("harness".to_string(), "0.1", |config| config);
+
+    let mut harness = TestHarness::new(ChargedJump::default());
+
+    harness.tap_key(KeyCode::Space);
+    harness.tick();
+
+    assert_eq!(harness.game.held_frames, 1);
+    assert_eq!(harness.game.weak_jumps, 1);
+    assert_eq!(harness.game.charged_jumps, 0);
+
+    // 30 ticks at 60 FPS is exactly the charge time.
+    harness.hold_key(KeyCode::Space, 30);
+    assert_eq!(harness.game.charged_jumps, 1);
+
+    harness.tick();
+    assert!(!is_key_down(KeyCode::Space));
+    assert_eq!(harness.game.held_frames, 31);
+    assert_eq!(harness.game.weak_jumps, 1);
+
+    harness.finish();
+}